@@ -0,0 +1,110 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use lox_rs::ast::{Arena, Expr, Program};
+use lox_rs::parser::Parser;
+use lox_rs::scanner::Scanner;
+use lox_rs::visit::{walk_expr, walk_stmts, Visitor};
+
+/// Counts heap allocations so the benchmark can report them per node.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    /// Forwarded so that growing a large vector can stay in place, as it
+    /// would without this wrapper.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const SNIPPET: &str = r#"fun fib(n) {
+    if (n <= 1) return n;
+    return fib(n - 2) + fib(n - 1);
+}
+
+class Point < Shape {
+    init(x, y) { this.x = x; this.y = y; }
+    length() { return (this.x * this.x + this.y * this.y) ** 0.5; }
+}
+
+var items = [1, 2, 3, 4 * 5 - 6 / 7, -8];
+var table = {"a": items[0], "b": items[1] ?? 0};
+for (var i = 0; i < 10; i = i + 1) {
+    print fib(i) % 3 == 0 ? "fizz" : "${i}";
+    total += point?.length() or 0 and !done;
+}
+"#;
+
+/// Counts expressions, standing in for a later pass over the tree.
+struct CountExprs<'a> {
+    exprs: &'a Arena,
+    count: usize,
+}
+
+impl<'a> Visitor<'a> for CountExprs<'a> {
+    fn exprs(&self) -> &'a Arena {
+        self.exprs
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.count += 1;
+        walk_expr(self, expr);
+    }
+}
+
+fn main() {
+    let mut source = String::new();
+    while source.lines().count() < 10_000 {
+        source.push_str(SNIPPET);
+    }
+    let (tokens, errors) = Scanner::new(&source).scan_tokens();
+    assert!(errors.is_empty());
+
+    let iterations = 20;
+    let mut parsing = Duration::ZERO;
+    let mut walking = Duration::ZERO;
+    let mut allocations = 0;
+    let mut exprs = 0;
+    for _ in 0..iterations {
+        let tokens = tokens.clone();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let started = Instant::now();
+        let program: Program = Parser::parse(tokens).unwrap();
+        parsing += started.elapsed();
+        allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+        let started = Instant::now();
+        let mut counter = CountExprs {
+            exprs: &program.exprs,
+            count: 0,
+        };
+        walk_stmts(&mut counter, &program.statements);
+        walking += started.elapsed();
+        exprs = counter.count;
+    }
+
+    println!(
+        "parsed {} lines ({} expressions) in {:.2?}, walked in {:.2?} ({:.2} allocations per expression)",
+        source.lines().count(),
+        exprs,
+        parsing / iterations,
+        walking / iterations,
+        allocations as f64 / (exprs * iterations as usize) as f64
+    );
+}
@@ -0,0 +1,65 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use lox_rs::scanner::Scanner;
+
+/// Counts heap allocations so the benchmark can report them per token.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const SNIPPET: &str = r#"// Compute some numbers.
+fun fib(n) {
+    if (n <= 1) return n; /* base case */
+    return fib(n - 2) + fib(n - 1);
+}
+
+class Counter {
+    init() { this.count = 0; }
+    tick() { this.count = this.count + 1.5; }
+}
+
+var greeting = "Hello, ${name}! Total: ${0x1F + 1_000}";
+for (var i = 0; i < 10; i = i + 1) print fib(i);
+"#;
+
+fn main() {
+    let mut source = String::new();
+    while source.len() < 1 << 20 {
+        source.push_str(SNIPPET);
+    }
+
+    let iterations = 20;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    let mut tokens = 0;
+    for _ in 0..iterations {
+        tokens += Scanner::new(&source).scan_tokens().0.len();
+    }
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    let megabytes = (source.len() * iterations) as f64 / (1 << 20) as f64;
+    println!(
+        "scanned {} tokens in {:.2?} ({:.1} MB/s, {:.2} allocations per token)",
+        tokens / iterations,
+        elapsed / iterations as u32,
+        megabytes / elapsed.as_secs_f64(),
+        allocations as f64 / tokens as f64
+    );
+}
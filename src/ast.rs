@@ -0,0 +1,896 @@
+use std::ops::{Index, IndexMut, Range};
+use std::rc::Rc;
+
+use crate::token::Token;
+
+pub use crate::dot::to_dot;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+/// A parsed program: its top-level statements, and the arena holding
+/// every expression in them. The arena is shared with the functions the
+/// program declares, which keep it alive after the program has run.
+#[derive(Debug, Default)]
+pub struct Program {
+    pub exprs: Rc<Arena>,
+    pub statements: Vec<Stmt>,
+}
+
+/// A handle to an expression in an `Arena`. It is only meaningful for
+/// the arena it was allocated in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExprId(u32);
+
+/// The expressions of a program, stored by value. Expressions and
+/// statements refer to their subexpressions by `ExprId`, so a tree is one
+/// allocation per arena rather than one per node.
+#[derive(Debug, Default)]
+pub struct Arena {
+    exprs: Vec<Expr>,
+}
+
+impl Arena {
+    pub fn alloc(&mut self, expr: Expr) -> ExprId {
+        let id = u32::try_from(self.exprs.len()).expect("fewer than 2^32 expressions");
+        self.exprs.push(expr);
+        ExprId(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exprs.is_empty()
+    }
+}
+
+impl Index<ExprId> for Arena {
+    type Output = Expr;
+
+    fn index(&self, id: ExprId) -> &Expr {
+        &self.exprs[id.0 as usize]
+    }
+}
+
+impl IndexMut<ExprId> for Arena {
+    fn index_mut(&mut self, id: ExprId) -> &mut Expr {
+        &mut self.exprs[id.0 as usize]
+    }
+}
+
+/// An expression and the byte range of source it was parsed from.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Range<usize>,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    Literal(Literal),
+    Grouping(ExprId),
+    Variable {
+        name: Token,
+    },
+    Assign {
+        name: Token,
+        value: ExprId,
+    },
+    Unary {
+        operator: Token,
+        right: ExprId,
+    },
+    /// Also the comma operator, which evaluates `left` for its side
+    /// effects and yields `right`.
+    Binary {
+        left: ExprId,
+        operator: Token,
+        right: ExprId,
+    },
+    /// `and`, `or` and `??`, which only evaluate `right` when they must.
+    /// `left ?? right` is `left` unless that is `nil`; `false` is kept.
+    Logical {
+        left: ExprId,
+        operator: Token,
+        right: ExprId,
+    },
+    /// `condition ? then_branch : else_branch`.
+    Conditional {
+        condition: ExprId,
+        question: Token,
+        then_branch: ExprId,
+        else_branch: ExprId,
+    },
+    /// `optional` for `callee?.(arguments)`, which yields `nil` without
+    /// evaluating the arguments when `callee` is `nil`.
+    Call {
+        callee: ExprId,
+        paren: Token,
+        arguments: Vec<ExprId>,
+        optional: bool,
+    },
+    /// `optional` for `object?.name`, which yields `nil` when `object` is
+    /// `nil`.
+    Get {
+        object: ExprId,
+        name: Token,
+        optional: bool,
+    },
+    /// `object.name = value`. A compound assignment such as `object.name +=
+    /// value` carries its binary `operator`, so `object` is evaluated once.
+    Set {
+        object: ExprId,
+        name: Token,
+        operator: Option<Token>,
+        value: ExprId,
+    },
+    /// `[a, b] = value`.
+    AssignPattern {
+        pattern: Pattern,
+        equals: Token,
+        value: ExprId,
+    },
+    /// `target++` or `target--`, which stores the new value but yields the
+    /// old one. `target` is a `Variable`, `Get` or `Index`.
+    Postfix {
+        target: ExprId,
+        operator: Token,
+    },
+    /// A chain of calls, indexing and property accesses containing a `?.`.
+    /// When an optional link finds `nil`, the rest of the chain is skipped
+    /// and the whole chain yields `nil`.
+    OptionalChain(ExprId),
+    /// `[a, b, c]`.
+    List {
+        elements: Vec<ExprId>,
+    },
+    /// `{key: value, ...}`. Keys are strings or numbers, and a map keeps
+    /// its entries in insertion order.
+    Map {
+        brace: Token,
+        entries: Vec<(ExprId, ExprId)>,
+    },
+    /// `object[index]`. Negative indices into a list count from its end,
+    /// and a key missing from a map reads as `nil`.
+    Index {
+        object: ExprId,
+        bracket: Token,
+        index: ExprId,
+    },
+    /// `object[index] = value`, with `operator` for compound forms as in
+    /// `Set`.
+    IndexSet {
+        object: ExprId,
+        bracket: Token,
+        index: ExprId,
+        operator: Option<Token>,
+        value: ExprId,
+    },
+    This {
+        keyword: Token,
+    },
+    Super {
+        keyword: Token,
+        method: Token,
+    },
+    /// `fun (params) { body }`, an anonymous function whose `name` is its
+    /// `fun` keyword.
+    Lambda(Rc<Function>),
+    /// A string with `${...}` segments; the parts are stringified and
+    /// concatenated in order.
+    Interpolation {
+        parts: Vec<ExprId>,
+    },
+}
+
+/// The target of a destructuring `var` or assignment. A list pattern binds
+/// its names to a list's elements by position; the value must be a list at
+/// least as long as the pattern, and extra elements are ignored.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Name(Token),
+    List(Vec<Pattern>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: Token,
+    pub span: Range<usize>,
+    pub params: Vec<Param>,
+    /// A final `...name`, bound to a list of the arguments left over after
+    /// `params`.
+    pub rest: Option<Token>,
+    pub return_type: Option<TypeAnnotation>,
+    pub body: Vec<Stmt>,
+}
+
+/// A parameter and its default, which is evaluated when a call leaves the
+/// parameter out, in a scope with the parameters before it bound. Defaults
+/// are trailing.
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: Token,
+    pub annotation: Option<TypeAnnotation>,
+    pub default: Option<ExprId>,
+}
+
+/// A `: Type` annotation on a variable, parameter or return value. The
+/// interpreter ignores these; they are kept for external checkers, and
+/// the span runs from the `:` to the end of the name.
+#[derive(Debug, Clone)]
+pub struct TypeAnnotation {
+    pub name: Token,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwitchCase {
+    pub value: ExprId,
+    pub body: Vec<Stmt>,
+}
+
+/// `catch (name) { body }`, with `name` bound to the thrown value. The
+/// span runs from `catch` to the closing brace.
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    pub name: Token,
+    pub span: Range<usize>,
+    pub body: Vec<Stmt>,
+}
+
+/// A statement and the byte range of source it was parsed from.
+#[derive(Debug, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Range<usize>,
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
+    Expression(ExprId),
+    Print(ExprId),
+    /// `var name = initializer;`, or `const` when `constant`, which
+    /// forbids assigning to the name later.
+    Var {
+        name: Token,
+        annotation: Option<TypeAnnotation>,
+        initializer: Option<ExprId>,
+        constant: bool,
+    },
+    /// `var [a, b] = initializer;`, or the `const` form.
+    VarPattern {
+        pattern: Pattern,
+        equals: Token,
+        initializer: ExprId,
+        constant: bool,
+    },
+    Block(Vec<Stmt>),
+    If {
+        keyword: Token,
+        condition: ExprId,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    /// Also the desugared form of `for`, whose increment runs after the body
+    /// and after every `continue`. `keyword` is the `while` or the `for`.
+    While {
+        keyword: Token,
+        condition: ExprId,
+        body: Box<Stmt>,
+        increment: Option<ExprId>,
+    },
+    /// Runs `body` once per list element, map key, string character or
+    /// value from an instance's `iterate()`, with `name` freshly bound each
+    /// time.
+    ForIn {
+        name: Token,
+        iterable: ExprId,
+        body: Box<Stmt>,
+    },
+    Break {
+        keyword: Token,
+    },
+    Continue {
+        keyword: Token,
+    },
+    /// Runs the body of the first case equal to `subject`, or `default`.
+    /// Cases don't fall through.
+    Switch {
+        keyword: Token,
+        subject: ExprId,
+        cases: Vec<SwitchCase>,
+        default: Option<Vec<Stmt>>,
+    },
+    Function(Rc<Function>),
+    Return {
+        keyword: Token,
+        value: Option<ExprId>,
+    },
+    /// `import "path";` or `import "path" as alias;`. Only allowed at the
+    /// top level of a program.
+    Import {
+        keyword: Token,
+        path: String,
+        alias: Option<Token>,
+    },
+    /// `throw value;`, which unwinds to the nearest enclosing `catch`.
+    Throw {
+        keyword: Token,
+        value: ExprId,
+    },
+    /// `try { body }` followed by a `catch`, a `finally` or both. `finally`
+    /// runs however `body` and `catch` are left.
+    Try {
+        keyword: Token,
+        body: Vec<Stmt>,
+        catch: Option<CatchClause>,
+        finally: Option<Vec<Stmt>>,
+    },
+    /// `fields` are the `Var` declarations in the class body. Their
+    /// initializers run for each new instance, in order and with `this`
+    /// bound, before `init`.
+    Class {
+        name: Token,
+        superclass: Option<ExprId>,
+        fields: Vec<Stmt>,
+        methods: Vec<Rc<Function>>,
+        static_methods: Vec<Rc<Function>>,
+    },
+}
+
+#[cfg(feature = "serde")]
+mod serialize {
+    //! The AST as JSON-like data. Each node is an object tagged with its
+    //! variant in `"type"` and followed by its `"span"`; names and
+    //! operators are their lexemes, and the tokens implied by the node,
+    //! like a call's closing paren, are left out.
+
+    use serde::ser::{Serialize, SerializeMap, Serializer};
+
+    use super::*;
+
+    /// Serializes the `key: value` pairs as an object, in order.
+    macro_rules! object {
+        ($serializer:expr, $($key:literal: $value:expr),* $(,)?) => {{
+            let mut object = $serializer.serialize_map(None)?;
+            $(object.serialize_entry($key, $value)?;)*
+            object.end()
+        }};
+    }
+
+    /// A token as its lexeme.
+    struct Name<'a>(&'a Token);
+
+    impl Serialize for Name<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.0.lexeme)
+        }
+    }
+
+    fn name(token: &Token) -> Name<'_> {
+        Name(token)
+    }
+
+    /// A node whose expressions are looked up in `exprs`.
+    trait SerializeIn {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>;
+    }
+
+    /// `T` with the arena its expressions are in.
+    struct In<'a, T: ?Sized>(&'a Arena, &'a T);
+
+    impl<T: SerializeIn + ?Sized> Serialize for In<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.1.serialize_in(self.0, serializer)
+        }
+    }
+
+    impl Serialize for Program {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            In(&self.exprs, &self.statements).serialize(serializer)
+        }
+    }
+
+    impl SerializeIn for ExprId {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            exprs[*self].serialize_in(exprs, serializer)
+        }
+    }
+
+    /// An entry of a map literal.
+    impl SerializeIn for (ExprId, ExprId) {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            object!(serializer, "key": &In(exprs, &self.0), "value": &In(exprs, &self.1))
+        }
+    }
+
+    impl<T: SerializeIn> SerializeIn for [T] {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter().map(|item| In(exprs, item)))
+        }
+    }
+
+    impl<T: SerializeIn> SerializeIn for Vec<T> {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            self.as_slice().serialize_in(exprs, serializer)
+        }
+    }
+
+    impl<T: SerializeIn> SerializeIn for Option<T> {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match self {
+                Some(value) => serializer.serialize_some(&In(exprs, value)),
+                None => serializer.serialize_none(),
+            }
+        }
+    }
+
+    impl<T: SerializeIn + ?Sized> SerializeIn for Box<T> {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            (**self).serialize_in(exprs, serializer)
+        }
+    }
+
+    impl<T: SerializeIn + ?Sized> SerializeIn for Rc<T> {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            (**self).serialize_in(exprs, serializer)
+        }
+    }
+
+    impl Serialize for Literal {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Literal::Nil => serializer.serialize_unit(),
+                Literal::Bool(b) => serializer.serialize_bool(*b),
+                Literal::Number(n) => serializer.serialize_f64(*n),
+                Literal::String(s) => serializer.serialize_str(s),
+            }
+        }
+    }
+
+    impl SerializeIn for Expr {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            let span = &self.span;
+            match &self.kind {
+                ExprKind::Literal(literal) => {
+                    object!(serializer, "type": "Literal", "span": span, "value": literal)
+                }
+                ExprKind::Grouping(expr) => {
+                    object!(serializer, "type": "Grouping", "span": span, "expression": &In(exprs, expr))
+                }
+                ExprKind::Variable { name: variable } => {
+                    object!(serializer, "type": "Variable", "span": span, "name": &name(variable))
+                }
+                ExprKind::Assign {
+                    name: variable,
+                    value,
+                } => object!(
+                    serializer,
+                    "type": "Assign",
+                    "span": span,
+                    "name": &name(variable),
+                    "value": &In(exprs, value),
+                ),
+                ExprKind::Unary { operator, right } => object!(
+                    serializer,
+                    "type": "Unary",
+                    "span": span,
+                    "operator": &name(operator),
+                    "right": &In(exprs, right),
+                ),
+                ExprKind::Binary {
+                    left,
+                    operator,
+                    right,
+                } => object!(
+                    serializer,
+                    "type": "Binary",
+                    "span": span,
+                    "left": &In(exprs, left),
+                    "operator": &name(operator),
+                    "right": &In(exprs, right),
+                ),
+                ExprKind::Logical {
+                    left,
+                    operator,
+                    right,
+                } => object!(
+                    serializer,
+                    "type": "Logical",
+                    "span": span,
+                    "left": &In(exprs, left),
+                    "operator": &name(operator),
+                    "right": &In(exprs, right),
+                ),
+                ExprKind::Conditional {
+                    condition,
+                    then_branch,
+                    else_branch,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "Conditional",
+                    "span": span,
+                    "condition": &In(exprs, condition),
+                    "then": &In(exprs, then_branch),
+                    "else": &In(exprs, else_branch),
+                ),
+                ExprKind::Call {
+                    callee,
+                    arguments,
+                    optional,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "Call",
+                    "span": span,
+                    "callee": &In(exprs, callee),
+                    "arguments": &In(exprs, arguments),
+                    "optional": optional,
+                ),
+                ExprKind::Get {
+                    object,
+                    name: property,
+                    optional,
+                } => object!(
+                    serializer,
+                    "type": "Get",
+                    "span": span,
+                    "object": &In(exprs, object),
+                    "name": &name(property),
+                    "optional": optional,
+                ),
+                ExprKind::OptionalChain(expr) => {
+                    object!(serializer, "type": "OptionalChain", "span": span, "expression": &In(exprs, expr))
+                }
+                ExprKind::Set {
+                    object,
+                    name: property,
+                    operator,
+                    value,
+                } => object!(
+                    serializer,
+                    "type": "Set",
+                    "span": span,
+                    "object": &In(exprs, object),
+                    "name": &name(property),
+                    "operator": &operator.as_ref().map(name),
+                    "value": &In(exprs, value),
+                ),
+                ExprKind::AssignPattern { pattern, value, .. } => object!(
+                    serializer,
+                    "type": "AssignPattern",
+                    "span": span,
+                    "pattern": pattern,
+                    "value": &In(exprs, value),
+                ),
+                ExprKind::Postfix { target, operator } => object!(
+                    serializer,
+                    "type": "Postfix",
+                    "span": span,
+                    "target": &In(exprs, target),
+                    "operator": &name(operator),
+                ),
+                ExprKind::List { elements } => {
+                    object!(serializer, "type": "List", "span": span, "elements": &In(exprs, elements))
+                }
+                ExprKind::Map { entries, .. } => {
+                    object!(serializer, "type": "Map", "span": span, "entries": &In(exprs, entries))
+                }
+                ExprKind::Index { object, index, .. } => object!(
+                    serializer,
+                    "type": "Index",
+                    "span": span,
+                    "object": &In(exprs, object),
+                    "index": &In(exprs, index),
+                ),
+                ExprKind::IndexSet {
+                    object,
+                    index,
+                    operator,
+                    value,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "IndexSet",
+                    "span": span,
+                    "object": &In(exprs, object),
+                    "index": &In(exprs, index),
+                    "operator": &operator.as_ref().map(name),
+                    "value": &In(exprs, value),
+                ),
+                ExprKind::This { .. } => object!(serializer, "type": "This", "span": span),
+                ExprKind::Super { method, .. } => {
+                    object!(serializer, "type": "Super", "span": span, "method": &name(method))
+                }
+                ExprKind::Lambda(function) => object!(
+                    serializer,
+                    "type": "Lambda",
+                    "span": span,
+                    "params": &In(exprs, &function.params),
+                    "rest": &function.rest.as_ref().map(name),
+                    "return_type": &function.return_type,
+                    "body": &In(exprs, &function.body),
+                ),
+                ExprKind::Interpolation { parts } => {
+                    object!(serializer, "type": "Interpolation", "span": span, "parts": &In(exprs, parts))
+                }
+            }
+        }
+    }
+
+    impl SerializeIn for Function {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            object!(
+                serializer,
+                "type": "Function",
+                "span": &self.span,
+                "name": &name(&self.name),
+                "params": &In(exprs, &self.params),
+                "rest": &self.rest.as_ref().map(name),
+                "return_type": &self.return_type,
+                "body": &In(exprs, &self.body),
+            )
+        }
+    }
+
+    impl Serialize for Pattern {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Pattern::Name(variable) => name(variable).serialize(serializer),
+                Pattern::List(elements) => elements.serialize(serializer),
+            }
+        }
+    }
+
+    impl Serialize for TypeAnnotation {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            object!(serializer, "name": &name(&self.name), "span": &self.span)
+        }
+    }
+
+    impl SerializeIn for SwitchCase {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            object!(serializer, "value": &In(exprs, &self.value), "body": &In(exprs, &self.body))
+        }
+    }
+
+    impl SerializeIn for CatchClause {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            object!(
+                serializer,
+                "name": &name(&self.name),
+                "span": &self.span,
+                "body": &In(exprs, &self.body),
+            )
+        }
+    }
+
+    impl SerializeIn for Param {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            object!(
+                serializer,
+                "name": &name(&self.name),
+                "annotation": &self.annotation,
+                "default": &In(exprs, &self.default),
+            )
+        }
+    }
+
+    impl SerializeIn for Stmt {
+        fn serialize_in<S: Serializer>(
+            &self,
+            exprs: &Arena,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            let span = &self.span;
+            match &self.kind {
+                StmtKind::Expression(expr) => {
+                    object!(serializer, "type": "Expression", "span": span, "expression": &In(exprs, expr))
+                }
+                StmtKind::Print(expr) => {
+                    object!(serializer, "type": "Print", "span": span, "expression": &In(exprs, expr))
+                }
+                StmtKind::Var {
+                    name: variable,
+                    annotation,
+                    initializer,
+                    constant,
+                } => object!(
+                    serializer,
+                    "type": "Var",
+                    "span": span,
+                    "name": &name(variable),
+                    "annotation": annotation,
+                    "initializer": &In(exprs, initializer),
+                    "constant": constant,
+                ),
+                StmtKind::VarPattern {
+                    pattern,
+                    initializer,
+                    constant,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "VarPattern",
+                    "span": span,
+                    "pattern": pattern,
+                    "initializer": &In(exprs, initializer),
+                    "constant": constant,
+                ),
+                StmtKind::Block(statements) => {
+                    object!(serializer, "type": "Block", "span": span, "statements": &In(exprs, statements))
+                }
+                StmtKind::If {
+                    condition,
+                    then_branch,
+                    else_branch,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "If",
+                    "span": span,
+                    "condition": &In(exprs, condition),
+                    "then": &In(exprs, then_branch),
+                    "else": &In(exprs, else_branch),
+                ),
+                StmtKind::While {
+                    condition,
+                    body,
+                    increment,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "While",
+                    "span": span,
+                    "condition": &In(exprs, condition),
+                    "body": &In(exprs, body),
+                    "increment": &In(exprs, increment),
+                ),
+                StmtKind::ForIn {
+                    name: variable,
+                    iterable,
+                    body,
+                } => object!(
+                    serializer,
+                    "type": "ForIn",
+                    "span": span,
+                    "name": &name(variable),
+                    "iterable": &In(exprs, iterable),
+                    "body": &In(exprs, body),
+                ),
+                StmtKind::Break { .. } => object!(serializer, "type": "Break", "span": span),
+                StmtKind::Continue { .. } => {
+                    object!(serializer, "type": "Continue", "span": span)
+                }
+                StmtKind::Switch {
+                    subject,
+                    cases,
+                    default,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "Switch",
+                    "span": span,
+                    "subject": &In(exprs, subject),
+                    "cases": &In(exprs, cases),
+                    "default": &In(exprs, default),
+                ),
+                StmtKind::Function(function) => function.serialize_in(exprs, serializer),
+                StmtKind::Return { value, .. } => {
+                    object!(serializer, "type": "Return", "span": span, "value": &In(exprs, value))
+                }
+                StmtKind::Import { path, alias, .. } => object!(
+                    serializer,
+                    "type": "Import",
+                    "span": span,
+                    "path": path,
+                    "alias": &alias.as_ref().map(name),
+                ),
+                StmtKind::Throw { value, .. } => {
+                    object!(serializer, "type": "Throw", "span": span, "value": &In(exprs, value))
+                }
+                StmtKind::Try {
+                    body,
+                    catch,
+                    finally,
+                    ..
+                } => object!(
+                    serializer,
+                    "type": "Try",
+                    "span": span,
+                    "body": &In(exprs, body),
+                    "catch": &In(exprs, catch),
+                    "finally": &In(exprs, finally),
+                ),
+                StmtKind::Class {
+                    name: class,
+                    superclass,
+                    fields,
+                    methods,
+                    static_methods,
+                } => object!(
+                    serializer,
+                    "type": "Class",
+                    "span": span,
+                    "name": &name(class),
+                    "superclass": &In(exprs, superclass),
+                    "fields": &In(exprs, fields),
+                    "methods": &In(exprs, methods),
+                    "static_methods": &In(exprs, static_methods),
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::ast::{Arena, Expr, ExprKind, Function, Literal, Pattern, Program, Stmt, StmtKind};
+use crate::token::TokenKind;
+use crate::visit::{walk_expr, walk_function, walk_stmt, Visitor};
+
+/// String literals longer than this many characters are cut short.
+const MAX_LITERAL_LEN: usize = 20;
+
+/// Renders the AST as a Graphviz `digraph`, with one node per expression,
+/// statement and function, and edges to children in source order. Nodes are
+/// numbered in the order they are visited.
+pub fn to_dot(program: &Program) -> String {
+    let mut printer = DotPrinter {
+        output: String::from("digraph ast {\n    node [shape=box];\n"),
+        nodes: 0,
+        parent: None,
+        exprs: &program.exprs,
+    };
+    program
+        .statements
+        .iter()
+        .for_each(|statement| printer.visit_stmt(statement));
+    printer.output.push_str("}\n");
+    printer.output
+}
+
+struct DotPrinter<'a> {
+    output: String,
+    nodes: usize,
+    parent: Option<usize>,
+    exprs: &'a Arena,
+}
+
+impl DotPrinter<'_> {
+    /// Adds a node under the current parent and makes it the parent while
+    /// `children` runs.
+    fn node(&mut self, label: &str, children: impl FnOnce(&mut Self)) {
+        let id = self.nodes;
+        self.nodes += 1;
+        self.output
+            .push_str(&format!("    n{} [label=\"{}\"];\n", id, escape(label)));
+        if let Some(parent) = self.parent {
+            self.output
+                .push_str(&format!("    n{} -> n{};\n", parent, id));
+        }
+
+        let parent = self.parent.replace(id);
+        children(self);
+        self.parent = parent;
+    }
+}
+
+impl<'a> Visitor<'a> for DotPrinter<'a> {
+    fn exprs(&self) -> &'a Arena {
+        self.exprs
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        let label = match &expr.kind {
+            ExprKind::Lambda(function) => return self.visit_function(function),
+            ExprKind::Literal(literal) => literal_label(literal),
+            ExprKind::Grouping(_) => "group".to_owned(),
+            ExprKind::Variable { name } => name.lexeme.to_string(),
+            ExprKind::Assign { name, .. } => format!("= {}", name.lexeme),
+            ExprKind::Unary { operator, .. }
+            | ExprKind::Binary { operator, .. }
+            | ExprKind::Logical { operator, .. } => operator.lexeme.to_string(),
+            ExprKind::Conditional { .. } => "?:".to_owned(),
+            ExprKind::Call { optional, .. } => if *optional { "?.call" } else { "call" }.to_owned(),
+            ExprKind::Get { name, optional, .. } => {
+                format!("{}{}", if *optional { "?." } else { "." }, name.lexeme)
+            }
+            ExprKind::Set { name, operator, .. } => {
+                let operator = operator.as_ref().map_or("", |operator| &operator.lexeme);
+                format!("{}= .{}", operator, name.lexeme)
+            }
+            ExprKind::AssignPattern { pattern, .. } => format!("= {}", pattern_label(pattern)),
+            ExprKind::Postfix { operator, .. } => format!("post{}", operator.lexeme),
+            ExprKind::OptionalChain(_) => "chain".to_owned(),
+            ExprKind::List { .. } => "list".to_owned(),
+            ExprKind::Map { .. } => "map".to_owned(),
+            ExprKind::Index { .. } => "index".to_owned(),
+            ExprKind::IndexSet { operator, .. } => {
+                let operator = operator.as_ref().map_or("", |operator| &operator.lexeme);
+                format!("{}= index", operator)
+            }
+            ExprKind::This { .. } => "this".to_owned(),
+            ExprKind::Super { method, .. } => format!("super.{}", method.lexeme),
+            ExprKind::Interpolation { .. } => "interpolate".to_owned(),
+        };
+        self.node(&label, |printer| walk_expr(printer, expr));
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let label = match &stmt.kind {
+            StmtKind::Function(function) => return self.visit_function(function),
+            StmtKind::Expression(_) => "expr".to_owned(),
+            StmtKind::Print(_) => "print".to_owned(),
+            StmtKind::Var { name, constant, .. } => {
+                let keyword = if *constant { "const" } else { "var" };
+                format!("{} {}", keyword, name.lexeme)
+            }
+            StmtKind::VarPattern {
+                pattern, constant, ..
+            } => {
+                let keyword = if *constant { "const" } else { "var" };
+                format!("{} {}", keyword, pattern_label(pattern))
+            }
+            StmtKind::Block(_) => "block".to_owned(),
+            StmtKind::If { .. } => "if".to_owned(),
+            StmtKind::While { .. } => "while".to_owned(),
+            StmtKind::ForIn { name, .. } => format!("for-in {}", name.lexeme),
+            StmtKind::Break { .. } => "break".to_owned(),
+            StmtKind::Continue { .. } => "continue".to_owned(),
+            StmtKind::Switch { .. } => "switch".to_owned(),
+            StmtKind::Return { .. } => "return".to_owned(),
+            StmtKind::Import { path, alias, .. } => match alias {
+                Some(alias) => format!("import \"{}\" as {}", path, alias.lexeme),
+                None => format!("import \"{}\"", path),
+            },
+            StmtKind::Throw { .. } => "throw".to_owned(),
+            StmtKind::Try { catch, .. } => match catch {
+                Some(catch) => format!("try catch({})", catch.name.lexeme),
+                None => "try".to_owned(),
+            },
+            StmtKind::Class { name, .. } => format!("class {}", name.lexeme),
+        };
+        self.node(&label, |printer| walk_stmt(printer, stmt));
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        let mut params: Vec<String> = function
+            .params
+            .iter()
+            .map(|param| param.name.lexeme.to_string())
+            .collect();
+        if let Some(rest) = &function.rest {
+            params.push(format!("...{}", rest.lexeme));
+        }
+        let name = match function.name.kind {
+            TokenKind::Fun => "lambda",
+            _ => &function.name.lexeme,
+        };
+        let label = format!("{}({})", name, params.join(", "));
+        self.node(&label, |printer| walk_function(printer, function));
+    }
+}
+
+fn literal_label(literal: &Literal) -> String {
+    match literal {
+        Literal::Nil => "nil".to_owned(),
+        Literal::Bool(b) => b.to_string(),
+        Literal::Number(n) => format!("{:?}", n),
+        Literal::String(s) if s.chars().count() > MAX_LITERAL_LEN => {
+            let prefix: String = s.chars().take(MAX_LITERAL_LEN).collect();
+            format!("\"{}…\"", prefix)
+        }
+        Literal::String(s) => format!("\"{}\"", s),
+    }
+}
+
+fn pattern_label(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Name(name) => name.lexeme.to_string(),
+        Pattern::List(elements) => {
+            let elements: Vec<String> = elements.iter().map(pattern_label).collect();
+            format!("[{}]", elements.join(", "))
+        }
+    }
+}
+
+/// Escapes `label` for a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::RuntimeError;
+use crate::token::Token;
+use crate::value::Value;
+
+/// The variables of one scope, with a link to the scope around it. The
+/// outermost environment holds the globals.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<Rc<str>, Value>,
+    /// The names in `values` declared with `const`, with the lines they
+    /// were declared on.
+    constants: HashMap<Rc<str>, usize>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new scope nested inside `enclosing`.
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            enclosing: Some(enclosing),
+            ..Self::default()
+        }
+    }
+
+    /// Defines `name` in this scope, replacing any earlier definition here
+    /// and shadowing any in enclosing scopes.
+    pub fn define(&mut self, name: impl Into<Rc<str>>, value: Value) {
+        let name = name.into();
+        self.constants.remove(&name);
+        self.values.insert(name, value);
+    }
+
+    /// Defines `name` like `define`, but as a constant declared where
+    /// `name` is.
+    pub fn define_constant(&mut self, name: &Token, value: Value) {
+        self.constants.insert(name.lexeme.clone(), name.line);
+        self.values.insert(name.lexeme.clone(), value);
+    }
+
+    /// Reads the nearest definition of `name`.
+    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
+        match (self.values.get(&name.lexeme), &self.enclosing) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(enclosing)) => enclosing.borrow().get(name),
+            (None, None) => Err(undefined_variable(name)),
+        }
+    }
+
+    /// Replaces the nearest definition of `name`. Assigning never defines
+    /// a new variable.
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        if let Some(line) = self.constants.get(&name.lexeme) {
+            let message = format!(
+                "Can't assign to constant '{}' declared on line {}.",
+                name.lexeme, line
+            );
+            return Err(RuntimeError::new(name, message));
+        }
+        match (self.values.get_mut(&name.lexeme), &self.enclosing) {
+            (Some(slot), _) => {
+                *slot = value;
+                Ok(())
+            }
+            (None, Some(enclosing)) => enclosing.borrow_mut().assign(name, value),
+            (None, None) => Err(undefined_variable(name)),
+        }
+    }
+
+    /// Reads `name` from this scope alone, for names the interpreter
+    /// binds itself.
+    pub fn get_here(&self, name: &str) -> Option<Value> {
+        self.values.get(name).cloned()
+    }
+
+    /// Reads `name` from the scope `depth` levels out, where the resolver
+    /// found its declaration.
+    pub fn get_at(&self, depth: usize, name: &Token) -> Result<Value, RuntimeError> {
+        match (depth, &self.enclosing) {
+            (0, _) => self
+                .values
+                .get(&name.lexeme)
+                .cloned()
+                .ok_or_else(|| undefined_variable(name)),
+            (_, Some(enclosing)) => enclosing.borrow().get_at(depth - 1, name),
+            (_, None) => Err(undefined_variable(name)),
+        }
+    }
+
+    /// Assigns `name` in the scope `depth` levels out.
+    pub fn assign_at(
+        &mut self,
+        depth: usize,
+        name: &Token,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        match (depth, &self.enclosing) {
+            (0, _) => match self.values.get_mut(&name.lexeme) {
+                Some(slot) => {
+                    *slot = value;
+                    Ok(())
+                }
+                None => Err(undefined_variable(name)),
+            },
+            (_, Some(enclosing)) => enclosing.borrow_mut().assign_at(depth - 1, name, value),
+            (_, None) => Err(undefined_variable(name)),
+        }
+    }
+}
+
+fn undefined_variable(name: &Token) -> RuntimeError {
+    RuntimeError::new(name, format!("Undefined variable '{}'.", name.lexeme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenKind;
+
+    fn name(name: &str) -> Token {
+        let kind = TokenKind::Identifier(name.into());
+        Token::new(kind, name.into(), 1, 1, 0..name.len())
+    }
+
+    fn global() -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment::new()))
+    }
+
+    #[test]
+    fn define_and_get() {
+        let mut environment = Environment::new();
+        environment.define("a", Value::Number(1.0));
+        assert_eq!(environment.get(&name("a")), Ok(Value::Number(1.0)));
+        assert_eq!(environment.get_here("a"), Some(Value::Number(1.0)));
+    }
+
+    #[test]
+    fn define_replaces_in_the_same_scope() {
+        let mut environment = Environment::new();
+        environment.define("a", Value::Number(1.0));
+        environment.define("a", Value::Number(2.0));
+        assert_eq!(environment.get(&name("a")), Ok(Value::Number(2.0)));
+    }
+
+    #[test]
+    fn undefined_variables() {
+        let outer = global();
+        let mut inner = Environment::with_enclosing(outer);
+        let error = inner.get(&name("a")).expect_err("a is undefined");
+        assert_eq!(error.message, "Undefined variable 'a'.");
+        let error = inner
+            .assign(&name("a"), Value::Nil)
+            .expect_err("assigning never defines");
+        assert_eq!(error.message, "Undefined variable 'a'.");
+        assert_eq!(inner.get_here("a"), None);
+    }
+
+    #[test]
+    fn inner_scopes_shadow_outer_ones() {
+        let outer = global();
+        outer.borrow_mut().define("a", Value::Number(1.0));
+        let mut inner = Environment::with_enclosing(outer.clone());
+        inner.define("a", Value::Number(2.0));
+        assert_eq!(inner.get(&name("a")), Ok(Value::Number(2.0)));
+        assert_eq!(outer.borrow().get(&name("a")), Ok(Value::Number(1.0)));
+
+        inner.assign(&name("a"), Value::Number(3.0)).unwrap();
+        assert_eq!(inner.get(&name("a")), Ok(Value::Number(3.0)));
+        assert_eq!(outer.borrow().get(&name("a")), Ok(Value::Number(1.0)));
+    }
+
+    #[test]
+    fn assign_reaches_the_nearest_enclosing_definition() {
+        let outer = global();
+        outer.borrow_mut().define("a", Value::Number(1.0));
+        let middle = Rc::new(RefCell::new(Environment::with_enclosing(outer.clone())));
+        let mut inner = Environment::with_enclosing(middle.clone());
+        inner.assign(&name("a"), Value::Number(2.0)).unwrap();
+        assert_eq!(outer.borrow().get(&name("a")), Ok(Value::Number(2.0)));
+        assert_eq!(middle.borrow().get_here("a"), None);
+        assert_eq!(inner.get_here("a"), None);
+    }
+
+    #[test]
+    fn resolved_depths() {
+        let outer = global();
+        outer.borrow_mut().define("a", Value::Number(1.0));
+        let mut inner = Environment::with_enclosing(outer.clone());
+        inner.define("a", Value::Number(2.0));
+        assert_eq!(inner.get_at(0, &name("a")), Ok(Value::Number(2.0)));
+        assert_eq!(inner.get_at(1, &name("a")), Ok(Value::Number(1.0)));
+
+        inner.assign_at(1, &name("a"), Value::Number(3.0)).unwrap();
+        assert_eq!(outer.borrow().get(&name("a")), Ok(Value::Number(3.0)));
+        assert_eq!(inner.get_at(0, &name("a")), Ok(Value::Number(2.0)));
+        assert!(inner.get_at(2, &name("a")).is_err());
+    }
+
+    #[test]
+    fn constants_cannot_be_assigned() {
+        let outer = global();
+        let mut declaration = name("a");
+        declaration.line = 3;
+        outer
+            .borrow_mut()
+            .define_constant(&declaration, Value::Number(1.0));
+        let mut inner = Environment::with_enclosing(outer.clone());
+        let error = inner
+            .assign(&name("a"), Value::Number(2.0))
+            .expect_err("a is a constant");
+        assert_eq!(
+            error.message,
+            "Can't assign to constant 'a' declared on line 3."
+        );
+        assert_eq!(outer.borrow().get(&name("a")), Ok(Value::Number(1.0)));
+
+        // A later `var` of the same name is an ordinary variable again.
+        outer.borrow_mut().define("a", Value::Number(3.0));
+        inner.assign(&name("a"), Value::Number(4.0)).unwrap();
+        assert_eq!(outer.borrow().get(&name("a")), Ok(Value::Number(4.0)));
+    }
+}
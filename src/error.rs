@@ -0,0 +1,264 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::token::{Token, TokenKind};
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    UnterminatedInterpolation,
+    UnterminatedComment,
+    InvalidEscape(char),
+    InvalidUnicodeEscape(String, &'static str),
+    MalformedNumber(String, String),
+    /// A `ScannerLimits` bound was hit: the limit's name and its value.
+    LimitExceeded(&'static str, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl ScanError {
+    pub fn new(kind: ScanErrorKind, line: usize, column: usize, offset: usize) -> Self {
+        Self {
+            kind,
+            line,
+            column,
+            offset,
+        }
+    }
+}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ScanErrorKind::*;
+        match &self.kind {
+            UnexpectedCharacter(c) => write!(f, "Unexpected character '{}'", c)?,
+            UnterminatedString => write!(f, "Unterminated string")?,
+            UnterminatedInterpolation => write!(f, "Unterminated string interpolation")?,
+            UnterminatedComment => write!(f, "Unterminated block comment")?,
+            InvalidEscape(c) => write!(f, "Unknown escape sequence '\\{}'", c.escape_debug())?,
+            InvalidUnicodeEscape(escape, reason) => {
+                write!(f, "Invalid unicode escape '{}': {}", escape, reason)?
+            }
+            MalformedNumber(lexeme, reason) => {
+                write!(f, "Malformed number '{}': {}", lexeme, reason)?
+            }
+            LimitExceeded(limit, max) => write!(f, "Exceeded the maximum {} of {}", limit, max)?,
+        }
+        write!(f, " at {}:{}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    /// The offending token's lexeme, or `None` at the end of input.
+    pub lexeme: Option<Rc<str>>,
+    pub line: usize,
+    pub column: usize,
+    pub span: Range<usize>,
+}
+
+impl ParseError {
+    pub fn new(token: &Token, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            lexeme: (token.kind != TokenKind::Eof).then(|| token.lexeme.clone()),
+            line: token.line,
+            column: token.column,
+            span: token.span.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}:{}] ", self.line, self.column)?;
+        match &self.lexeme {
+            Some(lexeme) => write!(f, "at '{}': {}", lexeme, self.message),
+            None => write!(f, "at end: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Whether a diagnostic stops the program from running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A misuse of a name found by the resolver, such as reading a local
+/// variable in its own initializer, or a likely mistake it warns about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    pub message: String,
+    pub lexeme: Rc<str>,
+    pub line: usize,
+    pub column: usize,
+    pub span: Range<usize>,
+    pub severity: Severity,
+}
+
+impl ResolveError {
+    pub fn new(token: &Token, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            lexeme: token.lexeme.clone(),
+            line: token.line,
+            column: token.column,
+            span: token.span.clone(),
+            severity: Severity::Error,
+        }
+    }
+
+    pub fn warning(token: &Token, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(token, message)
+        }
+    }
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Error => write!(
+                f,
+                "[{}:{}] at '{}': {}",
+                self.line, self.column, self.lexeme, self.message
+            ),
+            Severity::Warning => write!(f, "Warning [line {}]: {}", self.line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// An error raised while a program runs, at the token whose evaluation
+/// failed: an operator, a call's closing paren, a name and so on. A
+/// `throw` is one too, at its keyword.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+    pub line: usize,
+    pub span: Range<usize>,
+    /// The value thrown, or `None` for an error the interpreter raised.
+    pub thrown: Option<Value>,
+    /// The calls the error escaped, innermost first and ending with the
+    /// top-level script. Empty until the error leaves the code it was
+    /// raised in.
+    pub trace: Vec<StackFrame>,
+}
+
+impl RuntimeError {
+    pub fn new(token: &Token, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            line: token.line,
+            span: token.span.clone(),
+            thrown: None,
+            trace: Vec::new(),
+        }
+    }
+
+    /// `value` thrown by the `throw` at `keyword`.
+    pub fn thrown(keyword: &Token, value: Value) -> Self {
+        Self {
+            thrown: Some(value.clone()),
+            ..Self::new(keyword, format!("Uncaught exception: {}", value))
+        }
+    }
+
+    /// What a `catch` binds: the thrown value, or the message of an error
+    /// the interpreter raised.
+    pub fn value(&self) -> Value {
+        match &self.thrown {
+            Some(value) => value.clone(),
+            None => Value::from(self.message.as_str()),
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] RuntimeError: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// One call in a runtime error's trace: the function it was in, and the
+/// line that was running there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    /// The function or class called, `lambda` for an anonymous function,
+    /// or `script` for the top level.
+    pub function: String,
+    pub line: usize,
+}
+
+impl std::fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] in {}", self.line, self.function)
+    }
+}
+
+/// Any error or warning found before a program runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    Scan(ScanError),
+    Parse(ParseError),
+    Resolve(ResolveError),
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Diagnostic::Resolve(error) => error.severity,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::Scan(error) => error.fmt(f),
+            Diagnostic::Parse(error) => error.fmt(f),
+            Diagnostic::Resolve(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl From<ScanError> for Diagnostic {
+    fn from(error: ScanError) -> Self {
+        Diagnostic::Scan(error)
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Diagnostic::Parse(error)
+    }
+}
+
+impl From<ResolveError> for Diagnostic {
+    fn from(error: ResolveError) -> Self {
+        Diagnostic::Resolve(error)
+    }
+}
@@ -0,0 +1,1347 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ast::{
+    Arena, CatchClause, ExprId, ExprKind, Function, Literal, Pattern, Program, Stmt, StmtKind,
+};
+use crate::environment::Environment;
+use crate::error::{RuntimeError, StackFrame};
+use crate::methods;
+use crate::resolver::Locals;
+use crate::token::{Token, TokenKind};
+use crate::value::{Callable, Class, Instance, Key, LoxFunction, Map, NativeFunction, Value};
+
+type EvalResult<T> = Result<T, RuntimeError>;
+type ExecResult = Result<(), Unwind>;
+
+/// Why a statement stopped before running to its end: an error, a
+/// `return` carrying its value out to the call, or a `break` or `continue`
+/// on its way out to the innermost loop.
+enum Unwind {
+    Error(RuntimeError),
+    Return(Value),
+    TailCall(Box<TailCall>),
+    Break,
+    Continue,
+}
+
+/// `return callee(arguments)`, left for the call being returned from to
+/// make in its own place, so tail calls don't grow the stack or count
+/// towards the call depth.
+struct TailCall {
+    paren: Token,
+    callee: Value,
+    arguments: Vec<Value>,
+}
+
+/// A call to a Lox function or class in progress.
+struct Call {
+    function: Rc<str>,
+    /// The line of the call, in the code that made it.
+    line: usize,
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+/// Settings for how programs run.
+#[derive(Debug, Clone, Copy)]
+pub struct InterpreterOptions {
+    /// Let `/` and `%` by zero give infinity or NaN, as IEEE doubles do,
+    /// instead of raising "Division by zero."
+    pub ieee_division: bool,
+    /// Make `if`, loops, `?:`, `!`, `and` and `or` raise an error for
+    /// conditions that aren't booleans, instead of going by truthiness.
+    pub strict_conditions: bool,
+    /// The most calls to Lox functions and classes that can be in progress
+    /// at once. One more raises a stack overflow error, so the thread
+    /// running the interpreter needs a stack with room for this many.
+    pub max_call_depth: usize,
+}
+
+impl Default for InterpreterOptions {
+    fn default() -> Self {
+        Self {
+            ieee_division: false,
+            strict_conditions: false,
+            max_call_depth: 256,
+        }
+    }
+}
+
+/// The values a `for-in` loop visits.
+enum Items {
+    /// A snapshot of a list's elements, a map's keys or a string's
+    /// characters.
+    Snapshot(std::vec::IntoIter<Value>),
+    /// The object an instance's `iterate()` returned. Each call to its
+    /// `next()` gives the next value, until it returns `nil`.
+    Iterator(Rc<RefCell<Instance>>),
+}
+
+/// A tree-walking interpreter. Globals persist across calls to
+/// `interpret`, so a REPL can run one line at a time.
+pub struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    /// The innermost scope of the code being run.
+    environment: Rc<RefCell<Environment>>,
+    /// The resolution of the code being run, which is the program's or,
+    /// inside a call, that of the program that declared the function.
+    locals: Rc<Locals>,
+    /// The expressions of the same program.
+    exprs: Rc<Arena>,
+    /// The calls to Lox functions and classes in progress, innermost
+    /// last. A tail call takes over the frame of the call it ends.
+    calls: Vec<Call>,
+    /// The `try` statements being run within the current call. A `return`
+    /// inside one is never a tail call, as the `try` isn't done yet.
+    protected: usize,
+    options: InterpreterOptions,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::new_with_options(InterpreterOptions::default())
+    }
+
+    pub fn new_with_options(options: InterpreterOptions) -> Self {
+        let mut globals = Environment::new();
+        for native in natives() {
+            globals.define(
+                native.name,
+                Value::Callable(Callable::Native(Rc::new(native))),
+            );
+        }
+        let globals = Rc::new(RefCell::new(globals));
+        Self {
+            environment: globals.clone(),
+            globals,
+            locals: Rc::default(),
+            exprs: Rc::default(),
+            calls: Vec::new(),
+            protected: 0,
+            options,
+        }
+    }
+
+    /// Runs `program`, stopping at the first runtime error. `locals` is
+    /// the resolver's result for `program`. A `return` outside any
+    /// function ends the program.
+    pub fn interpret(&mut self, program: &Program, locals: Locals) -> Result<(), RuntimeError> {
+        self.locals = Rc::new(locals);
+        self.exprs = program.exprs.clone();
+        for statement in &program.statements {
+            match self.execute(statement) {
+                Ok(()) => {}
+                Err(Unwind::Error(mut error)) => {
+                    if error.trace.is_empty() {
+                        error.trace = self.trace(error.line);
+                    }
+                    return Err(error);
+                }
+                Err(Unwind::Return(_)) => break,
+                Err(Unwind::TailCall(_)) => {
+                    unreachable!("tail calls are only made inside calls")
+                }
+                Err(Unwind::Break | Unwind::Continue) => {
+                    unreachable!("the parser rejects loop control outside loops")
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> ExecResult {
+        match &stmt.kind {
+            StmtKind::Expression(expr) => {
+                self.evaluate(*expr)?;
+            }
+            StmtKind::Print(expr) => println!("{}", self.evaluate(*expr)?),
+            StmtKind::Var {
+                name,
+                initializer,
+                constant,
+                ..
+            } => {
+                let value = match initializer {
+                    Some(initializer) => self.evaluate(*initializer)?,
+                    None => Value::Nil,
+                };
+                self.define(name, value, *constant);
+            }
+            StmtKind::VarPattern {
+                pattern,
+                equals,
+                initializer,
+                constant,
+            } => {
+                let value = self.evaluate(*initializer)?;
+                destructure(equals, pattern, value, &mut |name, value| {
+                    self.define(name, value, *constant);
+                    Ok(())
+                })?;
+            }
+            StmtKind::Block(statements) => self.execute_block(statements, self.scope())?,
+            StmtKind::If {
+                keyword,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.evaluate(*condition)?;
+                if self.condition(keyword, &condition)? {
+                    self.execute(then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)?;
+                }
+            }
+            StmtKind::While {
+                keyword,
+                condition,
+                body,
+                increment,
+            } => loop {
+                let value = self.evaluate(*condition)?;
+                if !self.condition(keyword, &value)? {
+                    break;
+                }
+                if !iterate(self.execute(body))? {
+                    break;
+                }
+                if let Some(increment) = increment {
+                    self.evaluate(*increment)?;
+                }
+            },
+            StmtKind::ForIn {
+                name,
+                iterable,
+                body,
+            } => {
+                let mut items = self.items(name, *iterable)?;
+                while let Some(item) = self.next_item(name, &mut items)? {
+                    let mut scope = self.scope();
+                    scope.define(name.lexeme.clone(), item);
+                    if !iterate(self.execute_block(std::slice::from_ref(body), scope))? {
+                        break;
+                    }
+                }
+            }
+            StmtKind::Switch {
+                subject,
+                cases,
+                default,
+                ..
+            } => {
+                let subject = self.evaluate(*subject)?;
+                let mut body = default.as_deref();
+                for case in cases {
+                    if self.evaluate(case.value)? == subject {
+                        body = Some(&case.body);
+                        break;
+                    }
+                }
+                if let Some(body) = body {
+                    self.execute_block(body, self.scope())?;
+                }
+            }
+            StmtKind::Break { .. } => return Err(Unwind::Break),
+            StmtKind::Continue { .. } => return Err(Unwind::Continue),
+            StmtKind::Function(function) => {
+                let value = self.function(function);
+                self.define(&function.name, value, false);
+            }
+            StmtKind::Return {
+                value: Some(value), ..
+            } if self.protected == 0 && !self.calls.is_empty() => {
+                let exprs = self.exprs.clone();
+                let ExprKind::Call {
+                    callee,
+                    paren,
+                    arguments,
+                    optional,
+                } = &exprs[*value].kind
+                else {
+                    return Err(Unwind::Return(self.evaluate(*value)?));
+                };
+                let callee = match self.link(*callee)? {
+                    Some(Value::Nil) if *optional => None,
+                    callee => callee,
+                };
+                let Some(callee) = callee else {
+                    return Err(Unwind::Return(Value::Nil));
+                };
+                let arguments = arguments
+                    .iter()
+                    .map(|&argument| self.evaluate(argument))
+                    .collect::<EvalResult<Vec<_>>>()?;
+                return Err(Unwind::TailCall(Box::new(TailCall {
+                    paren: paren.clone(),
+                    callee,
+                    arguments,
+                })));
+            }
+            StmtKind::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.evaluate(*value)?,
+                    None => Value::Nil,
+                };
+                return Err(Unwind::Return(value));
+            }
+            StmtKind::Class {
+                name,
+                superclass,
+                fields,
+                methods,
+                static_methods,
+            } => {
+                let superclass = match superclass {
+                    Some(superclass) => match self.evaluate(*superclass)? {
+                        Value::Callable(Callable::Class(class)) => Some(class),
+                        _ => {
+                            let ExprKind::Variable { name } = &self.exprs[*superclass].kind else {
+                                unreachable!("the parser only allows a name as a superclass")
+                            };
+                            return Err(
+                                RuntimeError::new(name, "Superclass must be a class.").into()
+                            );
+                        }
+                    },
+                    None => None,
+                };
+                // Methods of a subclass close over a scope binding `super`.
+                let enclosing = superclass.as_ref().map(|superclass| {
+                    let mut scope = self.scope();
+                    scope.define(
+                        "super",
+                        Value::Callable(Callable::Class(superclass.clone())),
+                    );
+                    std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope)))
+                });
+                let closures = |methods: &[Rc<Function>], instance: bool| {
+                    methods
+                        .iter()
+                        .map(|method| {
+                            let mut closure = self.closure(method);
+                            closure.is_initializer = instance && &*method.name.lexeme == "init";
+                            (method.name.lexeme.clone(), Rc::new(closure))
+                        })
+                        .collect()
+                };
+                let class = Class {
+                    name: name.lexeme.clone(),
+                    superclass,
+                    methods: closures(methods, true),
+                    static_methods: closures(static_methods, false),
+                    fields: fields.clone(),
+                    closure: self.environment.clone(),
+                    locals: self.locals.clone(),
+                    exprs: self.exprs.clone(),
+                };
+                if let Some(enclosing) = enclosing {
+                    self.environment = enclosing;
+                }
+                self.define(
+                    name,
+                    Value::Callable(Callable::Class(Rc::new(class))),
+                    false,
+                );
+            }
+            StmtKind::Import { keyword, .. } => return Err(unsupported(keyword, "Imports").into()),
+            StmtKind::Throw { keyword, value } => {
+                let value = self.evaluate(*value)?;
+                return Err(RuntimeError::thrown(keyword, value).into());
+            }
+            // Errors the interpreter raises are caught like thrown values.
+            // `finally` runs however the rest is left, and leaving it early
+            // overrides that.
+            StmtKind::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.protected += 1;
+                let result = self.try_statement(body, catch.as_ref(), finally.as_deref());
+                self.protected -= 1;
+                return result;
+            }
+        }
+        Ok(())
+    }
+
+    fn try_statement(
+        &mut self,
+        body: &[Stmt],
+        catch: Option<&CatchClause>,
+        finally: Option<&[Stmt]>,
+    ) -> ExecResult {
+        let mut result = self.execute_block(body, self.scope());
+        if let (Err(Unwind::Error(error)), Some(catch)) = (&result, catch) {
+            let mut scope = self.scope();
+            scope.define(catch.name.lexeme.clone(), error.value());
+            result = self.execute_block(&catch.body, scope);
+        }
+        if let Some(finally) = finally {
+            self.execute_block(finally, self.scope())?;
+        }
+        result
+    }
+
+    /// A new scope nested in the current one.
+    fn scope(&self) -> Environment {
+        Environment::with_enclosing(self.environment.clone())
+    }
+
+    /// Runs `statements` in `environment`.
+    fn execute_block(&mut self, statements: &[Stmt], environment: Environment) -> ExecResult {
+        let environment = Rc::new(RefCell::new(environment));
+        let (locals, exprs) = (self.locals.clone(), self.exprs.clone());
+        self.within(environment, locals, exprs, |interpreter| {
+            statements
+                .iter()
+                .try_for_each(|statement| interpreter.execute(statement))
+        })
+    }
+
+    /// Runs `run` in `environment`, resolved by `locals`, with its
+    /// expressions in `exprs`, then returns to the current scope whether or
+    /// not it succeeds.
+    fn within<T>(
+        &mut self,
+        environment: Rc<RefCell<Environment>>,
+        locals: Rc<Locals>,
+        exprs: Rc<Arena>,
+        run: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let environment = std::mem::replace(&mut self.environment, environment);
+        let locals = std::mem::replace(&mut self.locals, locals);
+        let exprs = std::mem::replace(&mut self.exprs, exprs);
+        let result = run(self);
+        self.environment = environment;
+        self.locals = locals;
+        self.exprs = exprs;
+        result
+    }
+
+    /// Where a `for-in` loop over `iterable` gets its values, with `name`
+    /// the loop variable that errors are reported at.
+    fn items(&mut self, name: &Token, iterable: ExprId) -> EvalResult<Items> {
+        let snapshot = |values: Vec<Value>| Ok(Items::Snapshot(values.into_iter()));
+        match self.evaluate(iterable)? {
+            Value::List(list) => snapshot(list.borrow().clone()),
+            Value::Map(map) => {
+                snapshot(map.borrow().iter().map(|(key, _)| key.to_value()).collect())
+            }
+            Value::String(s) => snapshot(s.chars().map(Value::from).collect()),
+            Value::Instance(instance)
+                if instance.borrow().class.find_method("iterate").is_some() =>
+            {
+                match self.call_method(name, &instance, "iterate")? {
+                    Value::Instance(iterator) => Ok(Items::Iterator(iterator)),
+                    value => Err(RuntimeError::new(
+                        name,
+                        format!(
+                            "'iterate()' must return an instance, not {}.",
+                            value.type_name()
+                        ),
+                    )),
+                }
+            }
+            value => Err(RuntimeError::new(
+                name,
+                format!(
+                    "Can only iterate over lists, maps, strings and instances with an \
+                     'iterate()' method, not {}.",
+                    value.type_name()
+                ),
+            )),
+        }
+    }
+
+    /// The next value of `items`, or `None` once they run out.
+    fn next_item(&mut self, name: &Token, items: &mut Items) -> EvalResult<Option<Value>> {
+        match items {
+            Items::Snapshot(values) => Ok(values.next()),
+            Items::Iterator(iterator) => match self.call_method(name, iterator, "next")? {
+                Value::Nil => Ok(None),
+                value => Ok(Some(value)),
+            },
+        }
+    }
+
+    /// Calls the method `method` of `instance` with no arguments, for the
+    /// code at `token`.
+    fn call_method(
+        &mut self,
+        token: &Token,
+        instance: &Rc<RefCell<Instance>>,
+        method: &str,
+    ) -> EvalResult<Value> {
+        let mut name = token.clone();
+        name.lexeme = method.into();
+        let callee = get_property(instance, &name)?;
+        self.call(token, callee, vec![])
+    }
+
+    /// Whether `value` counts as true where `token` tests it. With
+    /// `strict_conditions`, only booleans can be tested.
+    fn condition(&self, token: &Token, value: &Value) -> EvalResult<bool> {
+        match value {
+            Value::Bool(b) => Ok(*b),
+            _ if self.options.strict_conditions => Err(RuntimeError::new(
+                token,
+                format!("Condition must be a boolean, got {}.", value.type_name()),
+            )),
+            _ => Ok(value.is_truthy()),
+        }
+    }
+
+    fn evaluate(&mut self, expr: ExprId) -> EvalResult<Value> {
+        let exprs = self.exprs.clone();
+        match &exprs[expr].kind {
+            ExprKind::Literal(literal) => Ok(match literal {
+                Literal::Nil => Value::Nil,
+                Literal::Bool(b) => Value::Bool(*b),
+                Literal::Number(n) => Value::Number(*n),
+                Literal::String(s) => Value::from(s.as_str()),
+            }),
+            ExprKind::Grouping(expr) => self.evaluate(*expr),
+            ExprKind::Variable { name } => self.lookup(name),
+            ExprKind::Assign { name, value } => {
+                let value = self.evaluate(*value)?;
+                self.assign(name, value.clone())?;
+                Ok(value)
+            }
+            ExprKind::Unary { operator, right } => {
+                let right = self.evaluate(*right)?;
+                match operator.kind {
+                    TokenKind::Bang => Ok(Value::Bool(!self.condition(operator, &right)?)),
+                    TokenKind::Minus => Ok(Value::Number(-number_operand(operator, &right)?)),
+                    _ => Ok(Value::Number(!integer_operand(operator, &right)? as f64)),
+                }
+            }
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(*left)?;
+                let right = self.evaluate(*right)?;
+                binary(operator, left, right, self.options)
+            }
+            ExprKind::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(*left)?;
+                let logical = matches!(operator.kind, TokenKind::Or | TokenKind::And);
+                let short_circuits = match operator.kind {
+                    TokenKind::Or => self.condition(operator, &left)?,
+                    TokenKind::And => !self.condition(operator, &left)?,
+                    _ => !matches!(left, Value::Nil),
+                };
+                if short_circuits {
+                    return Ok(left);
+                }
+                let right = self.evaluate(*right)?;
+                if logical {
+                    self.condition(operator, &right)?;
+                }
+                Ok(right)
+            }
+            ExprKind::Conditional {
+                condition,
+                question,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.evaluate(*condition)?;
+                if self.condition(question, &condition)? {
+                    self.evaluate(*then_branch)
+                } else {
+                    self.evaluate(*else_branch)
+                }
+            }
+            ExprKind::Call { .. } | ExprKind::Get { .. } | ExprKind::Index { .. } => {
+                Ok(self.link(expr)?.unwrap_or(Value::Nil))
+            }
+            ExprKind::OptionalChain(chain) => Ok(self.link(*chain)?.unwrap_or(Value::Nil)),
+            ExprKind::Set {
+                object,
+                name,
+                operator,
+                value,
+            } => {
+                let Value::Instance(instance) = self.evaluate(*object)? else {
+                    return Err(RuntimeError::new(name, "Only instances have fields."));
+                };
+                // As with variables, `a.b op= c` reads `a.b` before it
+                // evaluates `c`.
+                let value = match operator {
+                    Some(operator) => {
+                        let current = get_field(&instance.borrow().fields, name)?;
+                        let value = self.evaluate(*value)?;
+                        binary(operator, current, value, self.options)?
+                    }
+                    None => self.evaluate(*value)?,
+                };
+                let field = name.lexeme.clone();
+                instance.borrow_mut().fields.insert(field, value.clone());
+                Ok(value)
+            }
+            ExprKind::AssignPattern {
+                pattern,
+                equals,
+                value,
+            } => {
+                let value = self.evaluate(*value)?;
+                destructure(equals, pattern, value.clone(), &mut |name, value| {
+                    self.assign(name, value)
+                })?;
+                Ok(value)
+            }
+            ExprKind::Postfix { target, operator } => self.postfix(*target, operator),
+            ExprKind::List { elements } => {
+                let elements = elements
+                    .iter()
+                    .map(|&element| self.evaluate(element))
+                    .collect::<EvalResult<_>>()?;
+                Ok(Value::list(elements))
+            }
+            ExprKind::Map { brace, entries } => {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    let key = map_key(brace, &self.evaluate(*key)?)?;
+                    map.insert(key, self.evaluate(*value)?);
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+            ExprKind::IndexSet {
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+            } => {
+                let object = self.evaluate(*object)?;
+                let index = self.evaluate(*index)?;
+                let value = match operator {
+                    Some(operator) => {
+                        let current = get_index(bracket, &object, &index)?;
+                        let value = self.evaluate(*value)?;
+                        binary(operator, current, value, self.options)?
+                    }
+                    None => self.evaluate(*value)?,
+                };
+                set_index(bracket, &object, &index, value.clone())?;
+                Ok(value)
+            }
+            ExprKind::This { keyword } => self.lookup(keyword),
+            ExprKind::Super { keyword, method } => self.super_method(keyword, method),
+            ExprKind::Lambda(function) => Ok(self.function(function)),
+            ExprKind::Interpolation { parts } => {
+                let mut text = String::new();
+                for part in parts {
+                    text.push_str(&self.evaluate(*part)?.to_string());
+                }
+                Ok(Value::from(text.as_str()))
+            }
+        }
+    }
+
+    /// Evaluates a link in a chain of calls, property accesses and
+    /// indexing, or `None` when an optional link found `nil` and the rest
+    /// of the chain is skipped.
+    fn link(&mut self, expr: ExprId) -> EvalResult<Option<Value>> {
+        let exprs = self.exprs.clone();
+        match &exprs[expr].kind {
+            ExprKind::Call {
+                callee,
+                paren,
+                arguments,
+                optional,
+            } => {
+                let Some(callee) = self.link(*callee)? else {
+                    return Ok(None);
+                };
+                if *optional && matches!(callee, Value::Nil) {
+                    return Ok(None);
+                }
+                let arguments = arguments
+                    .iter()
+                    .map(|&argument| self.evaluate(argument))
+                    .collect::<EvalResult<Vec<_>>>()?;
+                self.call(paren, callee, arguments).map(Some)
+            }
+            ExprKind::Get {
+                object,
+                name,
+                optional,
+            } => match self.link(*object)? {
+                None => Ok(None),
+                Some(Value::Nil) if *optional => Ok(None),
+                Some(Value::Instance(instance)) => get_property(&instance, name).map(Some),
+                Some(Value::Callable(Callable::Class(class))) => {
+                    match class.find_static_method(&name.lexeme) {
+                        Some(method) => Ok(Some(Value::Callable(Callable::Function(method)))),
+                        None => Err(undefined_property(name)),
+                    }
+                }
+                Some(value @ (Value::List(_) | Value::Map(_) | Value::String(_))) => {
+                    methods::property(&value, &name.lexeme)
+                        .map(Some)
+                        .ok_or_else(|| undefined_property(name))
+                }
+                Some(_) => Err(RuntimeError::new(name, "Only instances have properties.")),
+            },
+            ExprKind::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let Some(object) = self.link(*object)? else {
+                    return Ok(None);
+                };
+                let index = self.evaluate(*index)?;
+                get_index(bracket, &object, &index).map(Some)
+            }
+            _ => self.evaluate(expr).map(Some),
+        }
+    }
+
+    /// Calls `callee`, reporting errors at the call's closing `paren`.
+    fn call(&mut self, paren: &Token, callee: Value, arguments: Vec<Value>) -> EvalResult<Value> {
+        let Value::Callable(callable) = callee else {
+            return Err(RuntimeError::new(
+                paren,
+                "Can only call functions and classes.",
+            ));
+        };
+        check_arity(paren, callable.arity(), arguments.len())?;
+        match callable {
+            Callable::Native(native) => {
+                (native.function)(&arguments).map_err(|message| RuntimeError::new(paren, message))
+            }
+            Callable::NativeMethod(method) => {
+                let arguments: Vec<_> = std::iter::once(method.receiver.clone())
+                    .chain(arguments)
+                    .collect();
+                (method.native.function)(&arguments)
+                    .map_err(|message| RuntimeError::new(paren, message))
+            }
+            Callable::Function(function) => {
+                self.frame(paren, frame_name(&function), |interpreter| {
+                    interpreter.call_function(&function, arguments)
+                })
+            }
+            Callable::Class(class) => self.frame(paren, class.name.clone(), |interpreter| {
+                interpreter.instantiate(&class, arguments)
+            }),
+        }
+    }
+
+    /// Runs a call to `function`, made at `paren`, as one more frame on
+    /// the call stack.
+    fn frame(
+        &mut self,
+        paren: &Token,
+        function: Rc<str>,
+        call: impl FnOnce(&mut Self) -> EvalResult<Value>,
+    ) -> EvalResult<Value> {
+        if self.calls.len() == self.options.max_call_depth {
+            return Err(RuntimeError::new(
+                paren,
+                format!(
+                    "Stack overflow: exceeded {} call frames.",
+                    self.options.max_call_depth
+                ),
+            ));
+        }
+        self.calls.push(Call {
+            function,
+            line: paren.line,
+        });
+        let mut result = call(self);
+        // Record the stack in an error leaving the call it was raised in,
+        // while the calls it escapes are still on it.
+        if let Err(error) = &mut result {
+            if error.trace.is_empty() {
+                error.trace = self.trace(error.line);
+            }
+        }
+        self.calls.pop();
+        result
+    }
+
+    /// The call stack as a trace, for an error raised at `line` in the
+    /// innermost call.
+    fn trace(&self, mut line: usize) -> Vec<StackFrame> {
+        let mut trace = Vec::with_capacity(self.calls.len() + 1);
+        for call in self.calls.iter().rev() {
+            trace.push(StackFrame {
+                function: call.function.to_string(),
+                line,
+            });
+            line = call.line;
+        }
+        trace.push(StackFrame {
+            function: "script".to_owned(),
+            line,
+        });
+        trace
+    }
+
+    /// A function value for `declaration` that closes over the current
+    /// scope.
+    fn function(&self, declaration: &Rc<Function>) -> Value {
+        Value::Callable(Callable::Function(Rc::new(self.closure(declaration))))
+    }
+
+    fn closure(&self, declaration: &Rc<Function>) -> LoxFunction {
+        LoxFunction {
+            declaration: declaration.clone(),
+            closure: self.environment.clone(),
+            locals: self.locals.clone(),
+            exprs: self.exprs.clone(),
+            is_initializer: false,
+        }
+    }
+
+    /// `super.method`: the method from the superclass of the class whose
+    /// method is running, bound to the same `this`.
+    fn super_method(&mut self, keyword: &Token, method: &Token) -> EvalResult<Value> {
+        // Like an unresolved `this`, an unresolved `super` is looked for
+        // among the globals, where it's never defined.
+        let Some(depth) = self.locals.depth(keyword) else {
+            return Err(RuntimeError::new(keyword, "Undefined variable 'super'."));
+        };
+        let Value::Callable(Callable::Class(superclass)) =
+            self.environment.borrow().get_at(depth, keyword)?
+        else {
+            unreachable!("'super' is always bound to a class");
+        };
+        // `this` is bound in the scope just inside the one binding `super`.
+        let mut this = keyword.clone();
+        this.lexeme = "this".into();
+        let Value::Instance(instance) = self.environment.borrow().get_at(depth - 1, &this)? else {
+            unreachable!("'this' is always bound to an instance");
+        };
+        match superclass.find_method(&method.lexeme) {
+            Some(found) => Ok(Value::Callable(Callable::Function(Rc::new(
+                found.bind(instance),
+            )))),
+            None => Err(undefined_property(method)),
+        }
+    }
+
+    /// A new instance of `class`. Its fields are initialized in order,
+    /// starting with those its superclasses declare, and then `init` runs
+    /// with `arguments`.
+    fn instantiate(&mut self, class: &Rc<Class>, arguments: Vec<Value>) -> EvalResult<Value> {
+        let instance = Rc::new(RefCell::new(Instance {
+            class: class.clone(),
+            fields: HashMap::new(),
+        }));
+        self.initialize_fields(class, &instance)?;
+        if let Some(init) = class.find_method("init") {
+            self.call_function(&init.bind(instance.clone()), arguments)?;
+        }
+        Ok(Value::Instance(instance))
+    }
+
+    fn initialize_fields(
+        &mut self,
+        class: &Class,
+        instance: &Rc<RefCell<Instance>>,
+    ) -> EvalResult<()> {
+        if let Some(superclass) = &class.superclass {
+            self.initialize_fields(superclass, instance)?;
+        }
+        let mut scope = Environment::with_enclosing(class.closure.clone());
+        scope.define("this", Value::Instance(instance.clone()));
+        let scope = Rc::new(RefCell::new(scope));
+        let (locals, exprs) = (class.locals.clone(), class.exprs.clone());
+        self.within(scope, locals, exprs, |interpreter| {
+            class
+                .fields
+                .iter()
+                .try_for_each(|field| interpreter.initialize_field(field, instance))
+        })
+    }
+
+    fn initialize_field(
+        &mut self,
+        field: &Stmt,
+        instance: &Rc<RefCell<Instance>>,
+    ) -> EvalResult<()> {
+        let mut set = |name: &Token, value| {
+            let field = name.lexeme.clone();
+            instance.borrow_mut().fields.insert(field, value);
+            Ok(())
+        };
+        match &field.kind {
+            StmtKind::Var {
+                name, initializer, ..
+            } => {
+                let value = match initializer {
+                    Some(initializer) => self.evaluate(*initializer)?,
+                    None => Value::Nil,
+                };
+                set(name, value)
+            }
+            StmtKind::VarPattern {
+                pattern,
+                equals,
+                initializer,
+                ..
+            } => {
+                let value = self.evaluate(*initializer)?;
+                destructure(equals, pattern, value, &mut set)
+            }
+            _ => unreachable!("the parser only puts `var` declarations in a class body"),
+        }
+    }
+
+    /// Calls `function`, then in a loop each Lox function that the last one
+    /// tail called, so a chain of tail calls runs in one frame of the call
+    /// stack. The caller has checked the first call's argument count.
+    fn call_function(
+        &mut self,
+        function: &LoxFunction,
+        mut arguments: Vec<Value>,
+    ) -> EvalResult<Value> {
+        let mut tail_call: Option<Rc<LoxFunction>> = None;
+        loop {
+            let function = tail_call.as_deref().unwrap_or(function);
+            let call = match self.run_function(function, arguments)? {
+                Ok(value) => return Ok(value),
+                Err(call) => *call,
+            };
+            match call.callee {
+                Value::Callable(Callable::Function(next)) => {
+                    check_arity(&call.paren, next.arity(), call.arguments.len())?;
+                    if let Some(frame) = self.calls.last_mut() {
+                        frame.function = frame_name(&next);
+                    }
+                    tail_call = Some(next);
+                    arguments = call.arguments;
+                }
+                // Natives and classes are called like any other callee.
+                callee => return self.call(&call.paren, callee, call.arguments),
+            }
+        }
+    }
+
+    /// Binds `arguments` in a new scope inside the function's closure,
+    /// evaluating defaults for the parameters they leave out, and runs its
+    /// body there. Gives its result or else the tail call it ended with.
+    fn run_function(
+        &mut self,
+        function: &LoxFunction,
+        arguments: Vec<Value>,
+    ) -> EvalResult<Result<Value, Box<TailCall>>> {
+        let declaration = &function.declaration;
+        let scope = Rc::new(RefCell::new(Environment::with_enclosing(
+            function.closure.clone(),
+        )));
+        let protected = std::mem::take(&mut self.protected);
+        let (locals, exprs) = (function.locals.clone(), function.exprs.clone());
+        let result = self.within(scope.clone(), locals, exprs, |interpreter| {
+            let mut arguments = arguments.into_iter();
+            for param in &declaration.params {
+                let value = match (arguments.next(), &param.default) {
+                    (Some(value), _) => value,
+                    (None, Some(default)) => interpreter.evaluate(*default)?,
+                    (None, None) => unreachable!("arity is checked before the call"),
+                };
+                scope.borrow_mut().define(param.name.lexeme.clone(), value);
+            }
+            if let Some(rest) = &declaration.rest {
+                let rest_value = Value::list(arguments.collect());
+                scope.borrow_mut().define(rest.lexeme.clone(), rest_value);
+            }
+            declaration
+                .body
+                .iter()
+                .try_for_each(|statement| interpreter.execute(statement))
+        });
+        self.protected = protected;
+        match result {
+            Err(Unwind::Error(error)) => Err(error),
+            // The resolver only allows a bare `return` in `init`.
+            _ if function.is_initializer => Ok(Ok(function
+                .closure
+                .borrow()
+                .get_here("this")
+                .expect("initializers are always bound"))),
+            Ok(()) => Ok(Ok(Value::Nil)),
+            Err(Unwind::Return(value)) => Ok(Ok(value)),
+            Err(Unwind::TailCall(call)) => Ok(Err(call)),
+            Err(Unwind::Break | Unwind::Continue) => {
+                unreachable!("the parser rejects loop control outside loops")
+            }
+        }
+    }
+
+    /// `target++` and `target--`: stores the stepped value and yields the
+    /// old one, evaluating the target's object and index once.
+    fn postfix(&mut self, target: ExprId, operator: &Token) -> EvalResult<Value> {
+        let step = |old: &Value| -> EvalResult<Value> {
+            let delta = match operator.kind {
+                TokenKind::PlusPlus => 1.0,
+                _ => -1.0,
+            };
+            Ok(Value::Number(number_operand(operator, old)? + delta))
+        };
+        let exprs = self.exprs.clone();
+        match &exprs[target].kind {
+            ExprKind::Variable { name } => {
+                let old = self.lookup(name)?;
+                self.assign(name, step(&old)?)?;
+                Ok(old)
+            }
+            ExprKind::Get { object, name, .. } => {
+                let Value::Instance(instance) = self.evaluate(*object)? else {
+                    return Err(RuntimeError::new(name, "Only instances have fields."));
+                };
+                let old = get_field(&instance.borrow().fields, name)?;
+                let new = step(&old)?;
+                instance
+                    .borrow_mut()
+                    .fields
+                    .insert(name.lexeme.clone(), new);
+                Ok(old)
+            }
+            ExprKind::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.evaluate(*object)?;
+                let index = self.evaluate(*index)?;
+                let old = get_index(bracket, &object, &index)?;
+                set_index(bracket, &object, &index, step(&old)?)?;
+                Ok(old)
+            }
+            _ => unreachable!("the parser only builds postfix on assignable targets"),
+        }
+    }
+
+    fn define(&mut self, name: &Token, value: Value, constant: bool) {
+        let mut environment = self.environment.borrow_mut();
+        if constant {
+            environment.define_constant(name, value);
+        } else {
+            environment.define(name.lexeme.clone(), value);
+        }
+    }
+
+    fn lookup(&self, name: &Token) -> EvalResult<Value> {
+        match self.locals.depth(name) {
+            Some(depth) => self.environment.borrow().get_at(depth, name),
+            None => self.globals.borrow().get(name),
+        }
+    }
+
+    fn assign(&mut self, name: &Token, value: Value) -> EvalResult<()> {
+        match self.locals.depth(name) {
+            Some(depth) => self.environment.borrow_mut().assign_at(depth, name, value),
+            None => self.globals.borrow_mut().assign(name, value),
+        }
+    }
+}
+
+/// Whether a loop goes on after its body ran with `result`. A `continue`
+/// still runs a `for` loop's increment.
+fn iterate(result: ExecResult) -> Result<bool, Unwind> {
+    match result {
+        Ok(()) | Err(Unwind::Continue) => Ok(true),
+        Err(Unwind::Break) => Ok(false),
+        Err(unwind) => Err(unwind),
+    }
+}
+
+fn check_arity(
+    paren: &Token,
+    (least, most): (usize, Option<usize>),
+    count: usize,
+) -> EvalResult<()> {
+    if count < least || most.is_some_and(|most| count > most) {
+        let expected = match most {
+            Some(most) if most == least => least.to_string(),
+            Some(most) => format!("{} to {}", least, most),
+            None => format!("at least {}", least),
+        };
+        return Err(RuntimeError::new(
+            paren,
+            format!("Expected {} arguments but got {}.", expected, count),
+        ));
+    }
+    Ok(())
+}
+
+/// How a call to `function` shows in a stack trace.
+fn frame_name(function: &LoxFunction) -> Rc<str> {
+    match function.declaration.name.kind {
+        TokenKind::Fun => Rc::from(function.name()),
+        _ => function.declaration.name.lexeme.clone(),
+    }
+}
+
+/// Applies a binary operator that evaluates both of its operands, as in
+/// `Binary` expressions and compound assignments.
+fn binary(
+    operator: &Token,
+    left: Value,
+    right: Value,
+    options: InterpreterOptions,
+) -> EvalResult<Value> {
+    use TokenKind::*;
+    let value = match operator.kind {
+        Comma => right,
+        EqualEqual => Value::Bool(left == right),
+        BangEqual => Value::Bool(left != right),
+        // A string concatenates with a string or a number, printed as
+        // `print` would. `nil` and booleans stay errors, on either side, as
+        // adding one to a string is more often a bug than intended.
+        Plus => match (&left, &right) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+            (Value::String(_), Value::String(_) | Value::Number(_))
+            | (Value::Number(_), Value::String(_)) => {
+                Value::from(format!("{}{}", left, right).as_str())
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    operator,
+                    "Operands must be two numbers, or a string and a string or number.",
+                ))
+            }
+        },
+        Ampersand | Pipe | Caret | LessLess | GreaterGreater => {
+            let (a, b) = (
+                integer_operand(operator, &left)?,
+                integer_operand(operator, &right)?,
+            );
+            let n = match operator.kind {
+                Ampersand => a & b,
+                Pipe => a | b,
+                Caret => a ^ b,
+                _ => {
+                    let Some(shift) = u32::try_from(b).ok().filter(|b| *b < 64) else {
+                        return Err(RuntimeError::new(
+                            operator,
+                            "Shift amount must be between 0 and 63.",
+                        ));
+                    };
+                    match operator.kind {
+                        LessLess => a << shift,
+                        _ => a >> shift,
+                    }
+                }
+            };
+            Value::Number(n as f64)
+        }
+        // Strings compare by code point, which for UTF-8 is byte order.
+        // Comparisons with NaN are all false.
+        Greater | GreaterEqual | Less | LessEqual => {
+            let ordering = match (&left, &right) {
+                (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                _ => {
+                    return Err(RuntimeError::new(
+                        operator,
+                        format!(
+                            "Can't compare {} with {}.",
+                            left.type_name(),
+                            right.type_name()
+                        ),
+                    ))
+                }
+            };
+            Value::Bool(ordering.is_some_and(|ordering| match operator.kind {
+                Greater => ordering.is_gt(),
+                GreaterEqual => ordering.is_ge(),
+                Less => ordering.is_lt(),
+                _ => ordering.is_le(),
+            }))
+        }
+        _ => {
+            let (a, b) = match (&left, &right) {
+                (Value::Number(a), Value::Number(b)) => (*a, *b),
+                _ => return Err(RuntimeError::new(operator, "Operands must be numbers.")),
+            };
+            if matches!(operator.kind, Slash | Percent) && b == 0.0 && !options.ieee_division {
+                return Err(RuntimeError::new(operator, "Division by zero."));
+            }
+            match operator.kind {
+                Minus => Value::Number(a - b),
+                Star => Value::Number(a * b),
+                Slash => Value::Number(a / b),
+                // Truncated, like Rust's: the result has the sign of `a`.
+                Percent => Value::Number(a % b),
+                _ => Value::Number(a.powf(b)),
+            }
+        }
+    };
+    Ok(value)
+}
+
+fn number_operand(operator: &Token, value: &Value) -> EvalResult<f64> {
+    match value {
+        Value::Number(n) => Ok(*n),
+        _ => Err(RuntimeError::new(operator, "Operand must be a number.")),
+    }
+}
+
+/// Bitwise operators work on numbers with no fractional part that fit
+/// in an `i64`.
+fn integer_operand(operator: &Token, value: &Value) -> EvalResult<i64> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => Ok(*n as i64),
+        _ => Err(RuntimeError::new(operator, "Operands must be integers.")),
+    }
+}
+
+fn get_field(fields: &HashMap<Rc<str>, Value>, name: &Token) -> EvalResult<Value> {
+    fields
+        .get(&name.lexeme)
+        .cloned()
+        .ok_or_else(|| undefined_property(name))
+}
+
+/// A field of `instance`, or else one of its class's methods with `this`
+/// bound to it.
+fn get_property(instance: &Rc<RefCell<Instance>>, name: &Token) -> EvalResult<Value> {
+    if let Some(value) = instance.borrow().fields.get(&name.lexeme) {
+        return Ok(value.clone());
+    }
+    let method = instance.borrow().class.find_method(&name.lexeme);
+    match method {
+        Some(method) => Ok(Value::Callable(Callable::Function(Rc::new(
+            method.bind(instance.clone()),
+        )))),
+        None => Err(undefined_property(name)),
+    }
+}
+
+fn undefined_property(name: &Token) -> RuntimeError {
+    RuntimeError::new(name, format!("Undefined property '{}'.", name.lexeme))
+}
+
+fn get_index(bracket: &Token, object: &Value, index: &Value) -> EvalResult<Value> {
+    match object {
+        Value::List(list) => {
+            let list = list.borrow();
+            Ok(list[list_index(bracket, index, list.len())?].clone())
+        }
+        Value::Map(map) => Ok(map
+            .borrow()
+            .get(&map_key(bracket, index)?)
+            .cloned()
+            .unwrap_or(Value::Nil)),
+        // By character, not byte.
+        Value::String(s) => {
+            let i = methods::string_index(index, s.chars().count())
+                .map_err(|message| RuntimeError::new(bracket, message))?;
+            Ok(Value::from(s.chars().nth(i).expect("index is in bounds")))
+        }
+        _ => Err(RuntimeError::new(
+            bracket,
+            "Only lists, maps and strings can be indexed.",
+        )),
+    }
+}
+
+fn set_index(bracket: &Token, object: &Value, index: &Value, value: Value) -> EvalResult<()> {
+    match object {
+        Value::List(list) => {
+            let mut list = list.borrow_mut();
+            let i = list_index(bracket, index, list.len())?;
+            list[i] = value;
+        }
+        Value::Map(map) => map.borrow_mut().insert(map_key(bracket, index)?, value),
+        Value::String(_) => return Err(RuntimeError::new(bracket, "Strings are immutable.")),
+        _ => {
+            return Err(RuntimeError::new(
+                bracket,
+                "Only lists and maps can be indexed.",
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Resolves a list index, counting negative indices from the end.
+fn list_index(bracket: &Token, index: &Value, len: usize) -> EvalResult<usize> {
+    methods::list_index(index, len).map_err(|message| RuntimeError::new(bracket, message))
+}
+
+fn map_key(token: &Token, value: &Value) -> EvalResult<Key> {
+    methods::map_key(value).map_err(|message| RuntimeError::new(token, message))
+}
+
+/// Binds the names in `pattern` to the matching parts of `value` through
+/// `bind`. The value must be a list at least as long as the pattern;
+/// errors are reported at the assignment's `equals`.
+fn destructure(
+    equals: &Token,
+    pattern: &Pattern,
+    value: Value,
+    bind: &mut impl FnMut(&Token, Value) -> EvalResult<()>,
+) -> EvalResult<()> {
+    match pattern {
+        Pattern::Name(name) => bind(name, value),
+        Pattern::List(patterns) => {
+            let Value::List(list) = &value else {
+                return Err(RuntimeError::new(
+                    equals,
+                    format!("Can only destructure a list, not {}.", value.type_name()),
+                ));
+            };
+            let elements = list.borrow().clone();
+            if elements.len() < patterns.len() {
+                return Err(RuntimeError::new(
+                    equals,
+                    format!(
+                        "Expected at least {} elements to destructure but got {}.",
+                        patterns.len(),
+                        elements.len()
+                    ),
+                ));
+            }
+            patterns
+                .iter()
+                .zip(elements)
+                .try_for_each(|(pattern, element)| destructure(equals, pattern, element, bind))
+        }
+    }
+}
+
+fn unsupported(token: &Token, feature: &str) -> RuntimeError {
+    RuntimeError::new(token, format!("{} are not supported yet.", feature))
+}
+
+fn natives() -> Vec<NativeFunction> {
+    vec![NativeFunction {
+        name: "clock",
+        arity: 0,
+        function: |_| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|error| error.to_string())?;
+            Ok(Value::Number(now.as_secs_f64()))
+        },
+    }]
+}
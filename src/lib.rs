@@ -0,0 +1,85 @@
+pub mod ast;
+pub mod dot;
+pub mod environment;
+pub mod error;
+pub mod interpreter;
+pub mod methods;
+pub mod parser;
+pub mod printer;
+pub mod resolver;
+pub mod scanner;
+pub mod source_map;
+pub mod token;
+pub mod value;
+pub mod visit;
+
+use error::Diagnostic;
+use parser::Parser;
+use resolver::Resolver;
+use scanner::Scanner;
+
+/// Finds the static errors and warnings in `source` without running it.
+/// Each stage is skipped when the one before fails, as its errors would
+/// mostly be knock-on.
+pub fn check(source: &str) -> Vec<Diagnostic> {
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    if !errors.is_empty() {
+        return errors.into_iter().map(Diagnostic::from).collect();
+    }
+
+    let program = match Parser::parse(tokens) {
+        Ok(program) => program,
+        Err(errors) => return errors.into_iter().map(Diagnostic::from).collect(),
+    };
+
+    match Resolver::resolve(&program) {
+        Ok((_, diagnostics)) | Err(diagnostics) => {
+            diagnostics.into_iter().map(Diagnostic::from).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error::Severity;
+
+    fn messages(source: &str) -> Vec<(Severity, String)> {
+        check(source)
+            .iter()
+            .map(|diagnostic| (diagnostic.severity(), diagnostic.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn a_clean_program_has_no_diagnostics() {
+        assert_eq!(messages("print 1 / 0; fun f(a) { return a; }"), []);
+    }
+
+    #[test]
+    fn warnings_alone() {
+        assert_eq!(
+            messages("fun f() { var unused; }"),
+            [(
+                Severity::Warning,
+                "Warning [line 1]: local variable 'unused' is never used.".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn every_static_error() {
+        let severities: Vec<_> = messages("return 1; print this; { var a; var a; print a; }")
+            .into_iter()
+            .map(|(severity, _)| severity)
+            .collect();
+        assert_eq!(severities, [Severity::Error; 3]);
+    }
+
+    #[test]
+    fn later_stages_are_skipped_after_errors() {
+        // The resolver would also report the top-level `return`.
+        assert!(matches!(check("return; @")[..], [Diagnostic::Scan(_)]));
+        assert!(matches!(check("return; print (;")[..], [Diagnostic::Parse(_)]));
+    }
+}
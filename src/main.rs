@@ -1,7 +1,22 @@
-mod scanner;
-mod token;
+use lox_rs::error::{ResolveError, Severity, StackFrame};
+use lox_rs::interpreter::{Interpreter, InterpreterOptions};
+use lox_rs::parser::Parser;
+use lox_rs::printer::AstPrinter;
+use lox_rs::resolver::Resolver;
+use lox_rs::scanner::Scanner;
 
-use scanner::Scanner;
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Run,
+    Tokens,
+    #[cfg(feature = "serde")]
+    TokensJson,
+    Ast,
+    #[cfg(feature = "serde")]
+    AstJson,
+    AstDot,
+    Check,
+}
 
 fn read_line() -> String {
     let mut result = String::new();
@@ -9,34 +24,228 @@ fn read_line() -> String {
     result.trim().to_string()
 }
 
-fn run_file<T: AsRef<std::path::Path>>(path: T) {
+fn run_file<T: AsRef<std::path::Path>>(
+    path: T,
+    mode: Mode,
+    warnings: bool,
+    options: InterpreterOptions,
+) {
     let program = String::from_utf8(std::fs::read(path).expect("Cannot read given file"))
         .expect("Wrong file encoding");
-    run(program);
+    let mut interpreter = Interpreter::new_with_options(options);
+    if let Err(code) = run(program, mode, warnings, &mut interpreter) {
+        std::process::exit(code);
+    }
 }
 
-fn run_prompt() {
+fn run_prompt(mode: Mode, warnings: bool, options: InterpreterOptions) {
+    let mut interpreter = Interpreter::new_with_options(options);
     loop {
         print!("> ");
         let line = read_line();
         if line.is_empty() {
             break;
         };
-        run(line);
+        let _ = run(line, mode, warnings, &mut interpreter);
+    }
+}
+
+/// Exit codes from `sysexits.h`: bad arguments, bad input, a failure
+/// while running, and no room to run at all.
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_SOFTWARE: i32 = 70;
+const EX_OSERR: i32 = 71;
+
+/// The interpreter thread's stack is `BASE_STACK_SIZE`, for the scanner,
+/// parser and top-level code, and `FRAME_STACK_SIZE` more per Lox call
+/// allowed. A call nests several interpreter calls per statement and
+/// expression around it, which take tens of kilobytes each in debug
+/// builds.
+const BASE_STACK_SIZE: usize = 64 << 20;
+const FRAME_STACK_SIZE: usize = if cfg!(debug_assertions) {
+    512 << 10
+} else {
+    64 << 10
+};
+/// The deepest `--max-call-depth`, which keeps the stack to a few
+/// gigabytes of address space at most.
+const MAX_CALL_DEPTH: usize = 4096;
+
+/// Rejects the command line, explaining why.
+fn usage(message: &str) -> ! {
+    eprintln!("Usage error: {}", message);
+    std::process::exit(EX_USAGE);
+}
+
+/// Prints a diagnostic, with warnings saying so themselves.
+fn report(diagnostic: impl std::fmt::Display, severity: Severity) {
+    match severity {
+        Severity::Error => eprintln!("Error: {}", diagnostic),
+        Severity::Warning => eprintln!("{}", diagnostic),
+    }
+}
+
+/// Runs of identical frames longer than this, as runaway recursion
+/// leaves, print their first frame and a count of the rest.
+const REPEATED_FRAMES_SHOWN: usize = 3;
+
+/// Prints a runtime error's trace, innermost call first.
+fn report_trace(trace: &[StackFrame]) {
+    for run in trace.chunk_by(|a, b| a == b) {
+        if run.len() > REPEATED_FRAMES_SHOWN {
+            eprintln!("{}", run[0]);
+            eprintln!("[repeated {} more times]", run.len() - 1);
+        } else {
+            run.iter().for_each(|frame| eprintln!("{}", frame));
+        }
     }
 }
 
-fn run<T: AsRef<str>>(source: T) {
+/// Prints the resolver's diagnostics, leaving out warnings unless asked.
+fn report_resolved(diagnostics: &[ResolveError], warnings: bool) {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| warnings || diagnostic.severity == Severity::Error)
+        .for_each(|diagnostic| report(diagnostic, diagnostic.severity));
+}
+
+/// Runs `source` in `mode`, or returns the exit code to fail with. Resolver
+/// warnings are only shown by `Mode::Check`, or when `warnings` is set.
+fn run<T: AsRef<str>>(
+    source: T,
+    mode: Mode,
+    warnings: bool,
+    interpreter: &mut Interpreter,
+) -> Result<(), i32> {
     let s = source.as_ref();
+    if mode == Mode::Check {
+        let diagnostics = lox_rs::check(s);
+        diagnostics
+            .iter()
+            .for_each(|diagnostic| report(diagnostic, diagnostic.severity()));
+        return if diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity() == Severity::Warning)
+        {
+            Ok(())
+        } else {
+            Err(EX_DATAERR)
+        };
+    }
+
     let scanner = Scanner::new(s);
-    let tokens = scanner.scan_tokens();
-    tokens.iter().for_each(|token| println!("{}", token));
+    let (tokens, errors) = scanner.scan_tokens();
+    if !errors.is_empty() {
+        errors
+            .iter()
+            .for_each(|error| eprintln!("Error: {}", error));
+        return Err(EX_DATAERR);
+    }
+
+    match mode {
+        Mode::Tokens => {
+            tokens.iter().for_each(|token| println!("{}", token));
+            return Ok(());
+        }
+        #[cfg(feature = "serde")]
+        Mode::TokensJson => {
+            let json = serde_json::to_string(&tokens).expect("tokens always serialize");
+            println!("{}", json);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let program = match Parser::parse(tokens) {
+        Ok(program) => program,
+        Err(errors) => {
+            errors
+                .iter()
+                .for_each(|error| eprintln!("Error: {}", error));
+            return Err(EX_DATAERR);
+        }
+    };
+
+    match mode {
+        Mode::Ast => print!("{}", AstPrinter::print(&program)),
+        #[cfg(feature = "serde")]
+        Mode::AstJson => {
+            let json = serde_json::to_string(&program).expect("programs always serialize");
+            println!("{}", json);
+        }
+        Mode::AstDot => print!("{}", lox_rs::ast::to_dot(&program)),
+        _ => {
+            let locals = match Resolver::resolve(&program) {
+                Ok((locals, diagnostics)) => {
+                    report_resolved(&diagnostics, warnings);
+                    locals
+                }
+                Err(diagnostics) => {
+                    report_resolved(&diagnostics, warnings);
+                    return Err(EX_DATAERR);
+                }
+            };
+            if let Err(error) = interpreter.interpret(&program, locals) {
+                eprintln!("{}", error);
+                report_trace(&error.trace);
+                return Err(EX_SOFTWARE);
+            }
+        }
+    }
+    Ok(())
 }
 
 fn main() {
-    if let Some(filename) = std::env::args().nth(1) {
-        run_file(filename);
-    } else {
-        run_prompt();
+    let mut mode = Mode::Run;
+    let mut warnings = false;
+    let mut options = InterpreterOptions::default();
+    let mut filename = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--tokens" => mode = Mode::Tokens,
+            #[cfg(feature = "serde")]
+            "--tokens-json" => mode = Mode::TokensJson,
+            #[cfg(not(feature = "serde"))]
+            "--tokens-json" => usage("--tokens-json needs lox-rs built with the serde feature."),
+            "--ast" => mode = Mode::Ast,
+            #[cfg(feature = "serde")]
+            "--ast-json" => mode = Mode::AstJson,
+            #[cfg(not(feature = "serde"))]
+            "--ast-json" => usage("--ast-json needs lox-rs built with the serde feature."),
+            "--ast-dot" => mode = Mode::AstDot,
+            "--check" => mode = Mode::Check,
+            "--warnings" => warnings = true,
+            "--ieee-division" => options.ieee_division = true,
+            "--strict-bool" => options.strict_conditions = true,
+            _ if arg.starts_with("--max-call-depth=") => {
+                match arg["--max-call-depth=".len()..].parse() {
+                    Ok(depth @ 1..=MAX_CALL_DEPTH) => options.max_call_depth = depth,
+                    _ => usage(&format!(
+                        "--max-call-depth takes a number from 1 to {}.",
+                        MAX_CALL_DEPTH
+                    )),
+                }
+            }
+            _ => filename = Some(arg),
+        }
+    }
+
+    // Run on a thread with room for `max_call_depth` calls, so the
+    // interpreter reports running out of them before the host stack
+    // overflows.
+    let stack_size = BASE_STACK_SIZE + options.max_call_depth * FRAME_STACK_SIZE;
+    let interpreter = std::thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || match filename {
+            Some(filename) => run_file(filename, mode, warnings, options),
+            None => run_prompt(mode, warnings, options),
+        })
+        .unwrap_or_else(|error| {
+            eprintln!("Cannot start the interpreter: {}", error);
+            std::process::exit(EX_OSERR);
+        });
+    if interpreter.join().is_err() {
+        std::process::exit(EX_SOFTWARE);
     }
 }
@@ -0,0 +1,209 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::value::{Callable, Key, Map, NativeFunction, NativeMethod, Value};
+
+/// The methods built into lists. Each takes the list as its first argument,
+/// ahead of those counted by `arity`.
+static LIST_METHODS: [NativeFunction; 5] = [
+    NativeFunction {
+        name: "push",
+        arity: 1,
+        function: |args| {
+            list(args).borrow_mut().push(args[1].clone());
+            Ok(Value::Nil)
+        },
+    },
+    NativeFunction {
+        name: "pop",
+        arity: 0,
+        function: |args| {
+            list(args)
+                .borrow_mut()
+                .pop()
+                .ok_or_else(|| "Can't pop from an empty list.".to_owned())
+        },
+    },
+    NativeFunction {
+        name: "insert",
+        arity: 2,
+        function: |args| {
+            let mut list = list(args).borrow_mut();
+            // The index can also be the length, to append.
+            let len = list.len();
+            let index = position("List", &args[1], len, len + 1, len + 1)?;
+            list.insert(index, args[2].clone());
+            Ok(Value::Nil)
+        },
+    },
+    NativeFunction {
+        name: "remove",
+        arity: 1,
+        function: |args| {
+            let mut list = list(args).borrow_mut();
+            let index = list_index(&args[1], list.len())?;
+            Ok(list.remove(index))
+        },
+    },
+    NativeFunction {
+        name: "contains",
+        arity: 1,
+        function: |args| Ok(Value::Bool(list(args).borrow().contains(&args[1]))),
+    },
+];
+
+/// The methods built into maps, which take the map first.
+static MAP_METHODS: [NativeFunction; 4] = [
+    NativeFunction {
+        name: "has",
+        arity: 1,
+        function: |args| {
+            let key = map_key(&args[1])?;
+            Ok(Value::Bool(map(args).borrow().get(&key).is_some()))
+        },
+    },
+    NativeFunction {
+        name: "remove",
+        arity: 1,
+        function: |args| {
+            let key = map_key(&args[1])?;
+            Ok(map(args).borrow_mut().remove(&key).unwrap_or(Value::Nil))
+        },
+    },
+    NativeFunction {
+        name: "keys",
+        arity: 0,
+        function: |args| {
+            let keys = map(args)
+                .borrow()
+                .iter()
+                .map(|(key, _)| key.to_value())
+                .collect();
+            Ok(Value::list(keys))
+        },
+    },
+    NativeFunction {
+        name: "values",
+        arity: 0,
+        function: |args| {
+            let values = map(args)
+                .borrow()
+                .iter()
+                .map(|(_, value)| value.clone())
+                .collect();
+            Ok(Value::list(values))
+        },
+    },
+];
+
+/// The methods built into strings, which take the string first.
+static STRING_METHODS: [NativeFunction; 1] = [NativeFunction {
+    name: "substring",
+    arity: 2,
+    function: |args| {
+        let Value::String(s) = &args[0] else {
+            unreachable!("string methods are only bound to strings");
+        };
+        // Either bound can be the length, and negative ones count back
+        // from it.
+        let len = s.chars().count();
+        let start = position("String", &args[1], len, len, len + 1)?;
+        let end = position("String", &args[2], len, len, len + 1)?;
+        if start > end {
+            return Err(format!(
+                "Substring start {} is after its end {}.",
+                start, end
+            ));
+        }
+        let substring: String = s.chars().skip(start).take(end - start).collect();
+        Ok(Value::from(substring.as_str()))
+    },
+}];
+
+/// The built-in property `name` of `receiver`: a value like a list's
+/// `length`, or a method bound to `receiver`.
+pub fn property(receiver: &Value, name: &str) -> Option<Value> {
+    let methods: &[NativeFunction] = match receiver {
+        Value::List(list) if name == "length" => {
+            return Some(Value::Number(list.borrow().len() as f64))
+        }
+        Value::List(_) => &LIST_METHODS,
+        Value::Map(map) if name == "length" => {
+            return Some(Value::Number(map.borrow().len() as f64))
+        }
+        Value::Map(_) => &MAP_METHODS,
+        Value::String(s) if name == "length" => {
+            return Some(Value::Number(s.chars().count() as f64))
+        }
+        Value::String(_) => &STRING_METHODS,
+        _ => return None,
+    };
+    let native = methods.iter().find(|method| method.name == name)?;
+    Some(Value::Callable(Callable::NativeMethod(Rc::new(
+        NativeMethod {
+            receiver: receiver.clone(),
+            native,
+        },
+    ))))
+}
+
+/// Where `index` points in a list of `len` elements. It must be an
+/// integer, and negative indices count back from the end.
+pub fn list_index(index: &Value, len: usize) -> Result<usize, String> {
+    position("List", index, len, len, len)
+}
+
+/// Like `list_index`, for the characters of a string of `len`.
+pub fn string_index(index: &Value, len: usize) -> Result<usize, String> {
+    position("String", index, len, len, len)
+}
+
+/// Resolves `index` into a `sequence` of `len`, where negative indices
+/// are counted back from `end` and the result must be below `limit`.
+fn position(
+    sequence: &str,
+    index: &Value,
+    len: usize,
+    end: usize,
+    limit: usize,
+) -> Result<usize, String> {
+    let not_integer = || format!("{} index must be an integer.", sequence);
+    let Value::Number(n) = index else {
+        return Err(not_integer());
+    };
+    if n.fract() != 0.0 {
+        return Err(not_integer());
+    }
+    let resolved = if *n < 0.0 { *n + end as f64 } else { *n };
+    if resolved < 0.0 || resolved >= limit as f64 {
+        return Err(format!(
+            "{} index {} is out of bounds for length {}.",
+            sequence, n, len
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+pub fn map_key(value: &Value) -> Result<Key, String> {
+    Key::from_value(value).ok_or_else(|| match value {
+        Value::Number(_) => "NaN can't be a map key.".to_owned(),
+        _ => format!(
+            "Map keys must be nil, booleans, numbers or strings, not {}.",
+            value.type_name()
+        ),
+    })
+}
+
+fn list(args: &[Value]) -> &Rc<RefCell<Vec<Value>>> {
+    match &args[0] {
+        Value::List(list) => list,
+        _ => unreachable!("list methods are only bound to lists"),
+    }
+}
+
+fn map(args: &[Value]) -> &Rc<RefCell<Map>> {
+    match &args[0] {
+        Value::Map(map) => map,
+        _ => unreachable!("map methods are only bound to maps"),
+    }
+}
@@ -0,0 +1,1490 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::ast::{
+    Arena, CatchClause, Expr, ExprId, ExprKind, Function, Literal, Param, Pattern, Program, Stmt,
+    StmtKind, SwitchCase, TypeAnnotation,
+};
+use crate::error::ParseError;
+use crate::token::{Token, TokenKind};
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// The most parameters a function can declare, counting a rest parameter,
+/// and the most arguments a call can pass.
+pub const MAX_ARITY: usize = 255;
+
+/// A recursive-descent parser over the scanner's tokens. Expressions are
+/// parsed by precedence climbing over the operators in `infix_rule`.
+pub struct Parser {
+    tokens: Vec<Token>,
+    current: usize,
+    exprs: Arena,
+    errors: Vec<ParseError>,
+    loop_depth: usize,
+    /// How many blocks enclose the current statement; imports need zero.
+    block_depth: usize,
+}
+
+impl Parser {
+    /// Parses a whole program. Comment tokens are ignored, so the scanner's
+    /// trivia mode output is accepted too.
+    pub fn parse(tokens: Vec<Token>) -> Result<Program, Vec<ParseError>> {
+        let mut tokens: Vec<Token> = tokens
+            .into_iter()
+            .filter(|token| !is_comment(&token.kind))
+            .collect();
+        if tokens
+            .last()
+            .is_none_or(|token| token.kind != TokenKind::Eof)
+        {
+            let (line, column, end) = tokens.last().map_or((1, 1, 0), |token| {
+                (token.line, token.column, token.span.end)
+            });
+            tokens.push(Token::new(
+                TokenKind::Eof,
+                "".into(),
+                line,
+                column,
+                end..end,
+            ));
+        }
+
+        let mut parser = Parser {
+            tokens,
+            current: 0,
+            exprs: Arena::default(),
+            errors: vec![],
+            loop_depth: 0,
+            block_depth: 0,
+        };
+
+        let mut statements = vec![];
+        while !parser.is_at_end() {
+            statements.extend(parser.declaration_or_synchronize());
+        }
+
+        if parser.errors.is_empty() {
+            Ok(Program {
+                exprs: Rc::new(parser.exprs),
+                statements,
+            })
+        } else {
+            Err(parser.errors)
+        }
+    }
+
+    /// Parses a declaration, or records its error and skips ahead to the
+    /// next statement boundary so later errors can still be reported.
+    fn declaration_or_synchronize(&mut self) -> Vec<Stmt> {
+        let start = self.current;
+        match self.declaration() {
+            Ok(statements) => statements,
+            Err(error) => {
+                self.errors.push(error);
+                // The error may sit on the keyword starting the next
+                // statement, e.g. after a missing `;`; keep it unless
+                // nothing was consumed.
+                if self.current == start {
+                    self.advance();
+                }
+                self.synchronize();
+                vec![]
+            }
+        }
+    }
+
+    /// Skips to the end of the statement an error was found in. A `{ ... }`
+    /// skipped over is discarded whole, as in a function whose parameters
+    /// failed to parse, so its contents and closing brace don't surface as
+    /// further errors. A `}` closing the enclosing block is left for it.
+    fn synchronize(&mut self) {
+        use TokenKind::*;
+        let mut depth = 0;
+        while !self.is_at_end() {
+            if depth == 0 && self.previous().kind == Semicolon {
+                return;
+            }
+
+            match self.peek().kind {
+                Class | Const | Fun | Var | For | If | Import | While | Print | Return | Switch
+                | Throw | Try
+                    if depth == 0 =>
+                {
+                    return
+                }
+                LeftBrace => depth += 1,
+                RightBrace if depth == 0 && self.block_depth > 0 => return,
+                RightBrace if depth == 1 => {
+                    self.advance();
+                    self.matches(&[Semicolon]);
+                    return;
+                }
+                RightBrace if depth > 0 => depth -= 1,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Usually one statement, but `var a, b;` declares each name
+    /// separately.
+    fn declaration(&mut self) -> ParseResult<Vec<Stmt>> {
+        let start = self.peek().span.start;
+        let kind = if self.matches(&[TokenKind::Class]) {
+            self.class_declaration()?
+        } else if self.check(&TokenKind::Fun)
+            && matches!(self.peek_next().kind, TokenKind::Identifier(_))
+        {
+            // Without a name, `fun` starts a lambda expression statement;
+            // with one, the statement is a declaration rather than an
+            // expression statement of a named function.
+            self.advance();
+            StmtKind::Function(self.function("function")?)
+        } else if self.matches(&[TokenKind::Var, TokenKind::Const]) {
+            return self.var_declaration();
+        } else if self.block_depth == 0 && self.matches(&[TokenKind::Import]) {
+            self.import_declaration()?
+        } else {
+            return Ok(vec![self.statement()?]);
+        };
+        Ok(vec![Stmt::new(kind, self.span_from(start))])
+    }
+
+    /// `import "path";` or `import "path" as alias;`, after the keyword.
+    fn import_declaration(&mut self) -> ParseResult<StmtKind> {
+        let keyword = self.previous().clone();
+        let TokenKind::String(path) = self.peek().kind.clone() else {
+            return Err(self.error(self.peek(), "Expect a string literal path after 'import'."));
+        };
+        self.advance();
+        let alias = if self.matches(&[TokenKind::As]) {
+            Some(self.consume_identifier("Expect module name after 'as'.")?)
+        } else {
+            None
+        };
+        self.consume(&TokenKind::Semicolon, "Expect ';' after import.")?;
+        Ok(StmtKind::Import {
+            keyword,
+            path,
+            alias,
+        })
+    }
+
+    fn class_declaration(&mut self) -> ParseResult<StmtKind> {
+        let name = self.consume_identifier("Expect class name.")?;
+
+        let superclass = if self.matches(&[TokenKind::Less]) {
+            let name = self.consume_identifier("Expect superclass name.")?;
+            let span = name.span.clone();
+            Some(self.expr(ExprKind::Variable { name }, span))
+        } else {
+            None
+        };
+
+        self.consume(&TokenKind::LeftBrace, "Expect '{' before class body.")?;
+        let mut fields = vec![];
+        let mut methods = vec![];
+        let mut static_methods = vec![];
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            if self.matches(&[TokenKind::Var]) {
+                fields.extend(self.var_declaration()?);
+            } else if self.matches(&[TokenKind::Static]) {
+                static_methods.push(self.function("method")?);
+            } else {
+                methods.push(self.function("method")?);
+            }
+        }
+        self.consume(&TokenKind::RightBrace, "Expect '}' after class body.")?;
+
+        Ok(StmtKind::Class {
+            name,
+            superclass,
+            fields,
+            methods,
+            static_methods,
+        })
+    }
+
+    fn function(&mut self, kind: &str) -> ParseResult<Rc<Function>> {
+        let name = self.consume_identifier(&format!("Expect {} name.", kind))?;
+        let start = name.span.start;
+        self.consume(
+            &TokenKind::LeftParen,
+            &format!("Expect '(' after {} name.", kind),
+        )?;
+        self.function_body(name, start, kind)
+    }
+
+    /// Parses the parameters and body of a function whose `(` has been
+    /// consumed.
+    fn function_body(
+        &mut self,
+        name: Token,
+        start: usize,
+        kind: &str,
+    ) -> ParseResult<Rc<Function>> {
+        let mut params = vec![];
+        let mut rest = None;
+        if !self.check(&TokenKind::RightParen) {
+            loop {
+                if params.len() == MAX_ARITY {
+                    let message = format!("Can't have more than {} parameters.", MAX_ARITY);
+                    self.errors.push(self.error(self.peek(), &message));
+                }
+                if self.matches(&[TokenKind::DotDotDot]) {
+                    rest = Some(self.consume_identifier("Expect rest parameter name.")?);
+                    if self.check(&TokenKind::Equal) {
+                        let message = "A rest parameter can't have a default.";
+                        return Err(self.error(self.peek(), message));
+                    }
+                    if self.check(&TokenKind::Comma) {
+                        let message = "A rest parameter must be the last parameter.";
+                        return Err(self.error(self.peek(), message));
+                    }
+                    break;
+                }
+
+                let name = self.consume_identifier("Expect parameter name.")?;
+                let annotation = self.annotation()?;
+                let default = if self.matches(&[TokenKind::Equal]) {
+                    Some(self.assignment()?)
+                } else {
+                    if params
+                        .last()
+                        .is_some_and(|param: &Param| param.default.is_some())
+                    {
+                        let message =
+                            "A parameter without a default can't follow one with a default.";
+                        self.errors.push(self.error(&name, message));
+                    }
+                    None
+                };
+                params.push(Param {
+                    name,
+                    annotation,
+                    default,
+                });
+                if !self.matches(&[TokenKind::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(&TokenKind::RightParen, "Expect ')' after parameters.")?;
+        let return_type = self.annotation()?;
+
+        self.consume(
+            &TokenKind::LeftBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+        // Loops around a declaration don't reach into its body.
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let body = self.block();
+        self.loop_depth = loop_depth;
+
+        Ok(Rc::new(Function {
+            name,
+            span: self.span_from(start),
+            params,
+            rest,
+            return_type,
+            body: body?,
+        }))
+    }
+
+    /// A `var` or `const` statement, after the keyword.
+    fn var_declaration(&mut self) -> ParseResult<Vec<Stmt>> {
+        let start = self.previous().span.start;
+        let constant = self.previous().kind == TokenKind::Const;
+        self.var_declarators(start, constant)
+    }
+
+    /// The declarators of a `var` statement starting at `start`: `name` or
+    /// `name = value`, or `[pattern] = value`, separated by commas. Each
+    /// becomes its own statement, in order, so later initializers can see
+    /// earlier names. A `constant` name needs an initializer.
+    fn var_declarators(&mut self, mut start: usize, constant: bool) -> ParseResult<Vec<Stmt>> {
+        let mut declarations = vec![];
+        loop {
+            let kind = if self.matches(&[TokenKind::LeftBracket]) {
+                let pattern = self.list_pattern()?;
+                let equals =
+                    self.consume(&TokenKind::Equal, "Expect '=' after destructuring pattern.")?;
+                StmtKind::VarPattern {
+                    pattern,
+                    equals,
+                    initializer: self.assignment()?,
+                    constant,
+                }
+            } else {
+                let name = self.consume_identifier("Expect variable name.")?;
+                let annotation = self.annotation()?;
+                let initializer = if self.matches(&[TokenKind::Equal]) {
+                    Some(self.assignment()?)
+                } else if constant {
+                    return Err(self.error(self.peek(), "Expect '=' after constant name."));
+                } else {
+                    None
+                };
+                StmtKind::Var {
+                    name,
+                    annotation,
+                    initializer,
+                    constant,
+                }
+            };
+            declarations.push(Stmt::new(kind, self.span_from(start)));
+
+            if !self.matches(&[TokenKind::Comma]) {
+                break;
+            }
+            start = self.peek().span.start;
+        }
+
+        self.consume(
+            &TokenKind::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+        if let Some(last) = declarations.last_mut() {
+            last.span.end = self.previous().span.end;
+        }
+        Ok(declarations)
+    }
+
+    /// An optional `: Type` after a name or parameter list. Any identifier
+    /// is accepted as a type.
+    fn annotation(&mut self) -> ParseResult<Option<TypeAnnotation>> {
+        if !self.matches(&[TokenKind::Colon]) {
+            return Ok(None);
+        }
+        let start = self.previous().span.start;
+        let name = self.consume_identifier("Expect type name after ':'.")?;
+        Ok(Some(TypeAnnotation {
+            name,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// The names of a `[a, [b, c]]` pattern after its `[`.
+    fn list_pattern(&mut self) -> ParseResult<Pattern> {
+        let mut elements = vec![];
+        while !self.check(&TokenKind::RightBracket) {
+            if self.matches(&[TokenKind::LeftBracket]) {
+                elements.push(self.list_pattern()?);
+            } else {
+                elements.push(Pattern::Name(
+                    self.consume_identifier("Expect variable name.")?,
+                ));
+            }
+            if !self.matches(&[TokenKind::Comma]) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightBracket, "Expect ']' after pattern.")?;
+        Ok(Pattern::List(elements))
+    }
+
+    fn statement(&mut self) -> ParseResult<Stmt> {
+        use TokenKind::*;
+        let start = self.peek().span.start;
+        let kind = match self.peek().kind {
+            For => {
+                self.advance();
+                self.for_statement()?
+            }
+            If => {
+                self.advance();
+                self.if_statement()?
+            }
+            Print => {
+                self.advance();
+                let value = self.expression()?;
+                self.consume(&Semicolon, "Expect ';' after value.")?;
+                StmtKind::Print(value)
+            }
+            Return => {
+                self.advance();
+                self.return_statement()?
+            }
+            While => {
+                self.advance();
+                self.while_statement()?
+            }
+            Switch => {
+                self.advance();
+                self.switch_statement()?
+            }
+            Break | Continue => {
+                let keyword = self.advance().clone();
+                self.loop_control(keyword)?
+            }
+            Throw => {
+                let keyword = self.advance().clone();
+                let value = self.expression()?;
+                self.consume(&Semicolon, "Expect ';' after thrown value.")?;
+                StmtKind::Throw { keyword, value }
+            }
+            Try => {
+                self.advance();
+                self.try_statement()?
+            }
+            LeftBrace => {
+                self.advance();
+                StmtKind::Block(self.block()?)
+            }
+            Case | Default => {
+                let message = format!("Can't use '{}' outside of a switch.", self.peek().lexeme);
+                return Err(self.error(self.peek(), &message));
+            }
+            // Top-level imports are parsed as declarations.
+            Import => {
+                let message = "Imports are only allowed at the top level.";
+                return Err(self.error(self.peek(), message));
+            }
+            Catch | Finally => {
+                let message = format!("Can't use '{}' without 'try'.", self.peek().lexeme);
+                return Err(self.error(self.peek(), &message));
+            }
+            _ => self.expression_statement()?,
+        };
+        Ok(Stmt::new(kind, self.span_from(start)))
+    }
+
+    /// Desugars `for (init; condition; increment) body` into a block holding
+    /// the initializer and a `While` that carries the increment.
+    fn for_statement(&mut self) -> ParseResult<StmtKind> {
+        let keyword = self.previous().clone();
+        let start = keyword.span.start;
+        self.consume(&TokenKind::LeftParen, "Expect '(' after 'for'.")?;
+
+        let initializer_start = self.peek().span.start;
+        let mut initializer = if self.matches(&[TokenKind::Semicolon]) {
+            vec![]
+        } else if self.matches(&[TokenKind::Var]) {
+            if matches!(self.peek().kind, TokenKind::Identifier(_))
+                && self.peek_next().kind == TokenKind::In
+            {
+                let name = self.advance().clone();
+                self.advance();
+                return self.for_in_statement(name);
+            }
+            self.var_declarators(initializer_start, false)?
+        } else {
+            let kind = self.expression_statement()?;
+            vec![Stmt::new(kind, self.span_from(initializer_start))]
+        };
+
+        let condition = if !self.check(&TokenKind::Semicolon) {
+            self.expression()?
+        } else {
+            let span = self.peek().span.clone();
+            self.expr(ExprKind::Literal(Literal::Bool(true)), span)
+        };
+        self.consume(&TokenKind::Semicolon, "Expect ';' after loop condition.")?;
+
+        let increment = if !self.check(&TokenKind::RightParen) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(&TokenKind::RightParen, "Expect ')' after for clauses.")?;
+
+        let body = self.loop_body()?;
+        let mut statement = StmtKind::While {
+            keyword,
+            condition,
+            body: Box::new(body),
+            increment,
+        };
+        if !initializer.is_empty() {
+            initializer.push(Stmt::new(statement, self.span_from(start)));
+            statement = StmtKind::Block(initializer);
+        }
+        Ok(statement)
+    }
+
+    /// `for (var name in iterable) body`, after the `in`.
+    fn for_in_statement(&mut self, name: Token) -> ParseResult<StmtKind> {
+        let iterable = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expect ')' after for-in iterable.")?;
+        let body = self.loop_body()?;
+
+        Ok(StmtKind::ForIn {
+            name,
+            iterable,
+            body: Box::new(body),
+        })
+    }
+
+    fn if_statement(&mut self) -> ParseResult<StmtKind> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenKind::LeftParen, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expect ')' after if condition.")?;
+
+        let then_branch = Box::new(self.statement()?);
+        let else_branch = if self.matches(&[TokenKind::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+
+        Ok(StmtKind::If {
+            keyword,
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    fn return_statement(&mut self) -> ParseResult<StmtKind> {
+        let keyword = self.previous().clone();
+        let value = if !self.check(&TokenKind::Semicolon) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenKind::Semicolon, "Expect ';' after return value.")?;
+        Ok(StmtKind::Return { keyword, value })
+    }
+
+    fn while_statement(&mut self) -> ParseResult<StmtKind> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenKind::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expect ')' after condition.")?;
+        let body = self.loop_body()?;
+
+        Ok(StmtKind::While {
+            keyword,
+            condition,
+            body: Box::new(body),
+            increment: None,
+        })
+    }
+
+    fn loop_body(&mut self) -> ParseResult<Stmt> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    /// `break` or `continue`; using either outside a loop is reported
+    /// without abandoning the parse.
+    fn loop_control(&mut self, keyword: Token) -> ParseResult<StmtKind> {
+        if self.loop_depth == 0 {
+            let message = format!("Can't use '{}' outside of a loop.", keyword.lexeme);
+            self.errors.push(self.error(&keyword, &message));
+        }
+
+        let message = format!("Expect ';' after '{}'.", keyword.lexeme);
+        self.consume(&TokenKind::Semicolon, &message)?;
+        if keyword.kind == TokenKind::Break {
+            Ok(StmtKind::Break { keyword })
+        } else {
+            Ok(StmtKind::Continue { keyword })
+        }
+    }
+
+    fn switch_statement(&mut self) -> ParseResult<StmtKind> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenKind::LeftParen, "Expect '(' after 'switch'.")?;
+        let subject = self.expression()?;
+        self.consume(&TokenKind::RightParen, "Expect ')' after switch value.")?;
+        self.consume(&TokenKind::LeftBrace, "Expect '{' before switch body.")?;
+
+        let mut cases = vec![];
+        let mut default = None;
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            if self.matches(&[TokenKind::Case]) {
+                let value = self.expression()?;
+                self.consume(&TokenKind::Colon, "Expect ':' after case value.")?;
+                let body = self.case_body()?;
+                cases.push(SwitchCase { value, body });
+            } else if self.matches(&[TokenKind::Default]) {
+                if default.is_some() {
+                    let error = self.error(self.previous(), "A switch can only have one default.");
+                    self.errors.push(error);
+                }
+                self.consume(&TokenKind::Colon, "Expect ':' after 'default'.")?;
+                default = Some(self.case_body()?);
+            } else {
+                return Err(self.error(self.peek(), "Expect 'case' or 'default'."));
+            }
+        }
+        self.consume(&TokenKind::RightBrace, "Expect '}' after switch body.")?;
+
+        Ok(StmtKind::Switch {
+            keyword,
+            subject,
+            cases,
+            default,
+        })
+    }
+
+    fn case_body(&mut self) -> ParseResult<Vec<Stmt>> {
+        let mut statements = vec![];
+        self.block_depth += 1;
+        while ![TokenKind::Case, TokenKind::Default, TokenKind::RightBrace]
+            .iter()
+            .any(|kind| self.check(kind))
+            && !self.is_at_end()
+        {
+            statements.extend(self.declaration_or_synchronize());
+        }
+        self.block_depth -= 1;
+        Ok(statements)
+    }
+
+    /// `try { ... }`, then `catch (name) { ... }`, `finally { ... }` or
+    /// both.
+    fn try_statement(&mut self) -> ParseResult<StmtKind> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenKind::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let catch = if self.check(&TokenKind::Catch) {
+            let start = self.advance().span.start;
+            self.consume(&TokenKind::LeftParen, "Expect '(' after 'catch'.")?;
+            if self.check(&TokenKind::RightParen) {
+                return Err(self.error(self.peek(), "A catch clause must bind exactly one name."));
+            }
+            let name = self.consume_identifier("Expect exception variable name.")?;
+            if self.check(&TokenKind::Comma) {
+                return Err(self.error(self.peek(), "A catch clause must bind exactly one name."));
+            }
+            self.consume(
+                &TokenKind::RightParen,
+                "Expect ')' after exception variable.",
+            )?;
+            self.consume(&TokenKind::LeftBrace, "Expect '{' before catch body.")?;
+            let body = self.block()?;
+            Some(CatchClause {
+                name,
+                span: self.span_from(start),
+                body,
+            })
+        } else {
+            None
+        };
+
+        let finally = if self.matches(&[TokenKind::Finally]) {
+            self.consume(&TokenKind::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error(self.peek(), "Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(StmtKind::Try {
+            keyword,
+            body,
+            catch,
+            finally,
+        })
+    }
+
+    fn block(&mut self) -> ParseResult<Vec<Stmt>> {
+        let mut statements = vec![];
+        self.block_depth += 1;
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            statements.extend(self.declaration_or_synchronize());
+        }
+        self.block_depth -= 1;
+
+        self.consume(&TokenKind::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
+    fn expression_statement(&mut self) -> ParseResult<StmtKind> {
+        let expr = self.expression()?;
+        self.consume(&TokenKind::Semicolon, "Expect ';' after expression.")?;
+        Ok(StmtKind::Expression(expr))
+    }
+
+    fn expression(&mut self) -> ParseResult<ExprId> {
+        self.parse_precedence(Precedence::Comma)
+    }
+
+    /// Argument lists and variable initializers parse from here, so their
+    /// commas stay separators.
+    fn assignment(&mut self) -> ParseResult<ExprId> {
+        self.parse_precedence(Precedence::Assignment)
+    }
+
+    /// Parses an expression whose infix operators all bind at least as
+    /// tightly as `min`: a prefix expression, then as many operators from
+    /// `infix_rule` as qualify.
+    fn parse_precedence(&mut self, min: Precedence) -> ParseResult<ExprId> {
+        let mut expr = self.prefix()?;
+        while let Some(rule) = infix_rule(&self.peek().kind) {
+            if rule.precedence < min {
+                break;
+            }
+            let operator = self.advance().clone();
+            expr = self.infix(expr, operator, rule)?;
+        }
+        Ok(expr)
+    }
+
+    fn prefix(&mut self) -> ParseResult<ExprId> {
+        use TokenKind::*;
+        if self.matches(&[Bang, Minus, Tilde]) {
+            let operator = self.previous().clone();
+            let right = self.parse_precedence(Precedence::Unary)?;
+            let span = operator.span.start..self.exprs[right].span.end;
+            return Ok(self.expr(ExprKind::Unary { operator, right }, span));
+        }
+
+        // Prefix `++x` and `--x` are `x += 1` and `x -= 1`.
+        if self.matches(&[PlusPlus, MinusMinus]) {
+            let operator = self.previous().clone();
+            let target = self.parse_precedence(Precedence::Unary)?;
+            let span = operator.span.start..self.exprs[target].span.end;
+            let one = self.expr(
+                ExprKind::Literal(Literal::Number(1.0)),
+                operator.span.clone(),
+            );
+            let compound = compound_operator(&operator);
+            return Ok(self.assign_to(target, compound, one, &operator, span));
+        }
+
+        self.call()
+    }
+
+    /// Parses the rest of an infix or postfix expression whose `operator`
+    /// has just been consumed.
+    fn infix(&mut self, left: ExprId, operator: Token, rule: InfixRule) -> ParseResult<ExprId> {
+        let start = self.exprs[left].span.start;
+        let kind = match rule.parse {
+            Infix::Binary | Infix::Logical | Infix::Power => {
+                let right = self.parse_precedence(rule.operand())?;
+                let span = start..self.exprs[right].span.end;
+                let kind = match rule.parse {
+                    Infix::Logical => ExprKind::Logical {
+                        left,
+                        operator,
+                        right,
+                    },
+                    _ => ExprKind::Binary {
+                        left,
+                        operator,
+                        right,
+                    },
+                };
+                return Ok(self.expr(kind, span));
+            }
+            Infix::Assignment => {
+                let value = self.parse_precedence(rule.operand())?;
+                let compound = compound_operator(&operator);
+                let span = start..self.exprs[value].span.end;
+                return Ok(self.assign_to(left, compound, value, &operator, span));
+            }
+            Infix::Conditional => {
+                let then_branch = self.expression()?;
+                self.consume(
+                    &TokenKind::Colon,
+                    "Expect ':' after then-branch of conditional.",
+                )?;
+                let else_branch = self.parse_precedence(rule.operand())?;
+                ExprKind::Conditional {
+                    condition: left,
+                    question: operator,
+                    then_branch,
+                    else_branch,
+                }
+            }
+            Infix::Postfix => {
+                if !matches!(
+                    self.exprs[left].kind,
+                    ExprKind::Variable { .. } | ExprKind::Get { .. } | ExprKind::Index { .. }
+                ) {
+                    self.errors
+                        .push(self.error(&operator, "Invalid assignment target."));
+                    return Ok(left);
+                }
+                ExprKind::Postfix {
+                    target: left,
+                    operator,
+                }
+            }
+        };
+        let span = self.span_from(start);
+        Ok(self.expr(kind, span))
+    }
+
+    /// Builds an assignment of `value` to `target`, combined with the
+    /// current value through `operator` for compound forms. A variable
+    /// target desugars to `name = name op value`; a field keeps the
+    /// operator so its object is evaluated once. A list of variables, such
+    /// as `[a, b] = pair`, destructures. Targets other than a variable are
+    /// replaced in the arena by the assignment they become.
+    fn assign_to(
+        &mut self,
+        target: ExprId,
+        operator: Option<Token>,
+        value: ExprId,
+        at: &Token,
+        span: Range<usize>,
+    ) -> ExprId {
+        let kind = match self.exprs[target].kind.clone() {
+            ExprKind::Variable { name } => {
+                let value = match operator {
+                    Some(operator) => {
+                        let span = self.exprs[target].span.start..self.exprs[value].span.end;
+                        let binary = ExprKind::Binary {
+                            left: target,
+                            operator,
+                            right: value,
+                        };
+                        self.expr(binary, span)
+                    }
+                    None => value,
+                };
+                return self.expr(ExprKind::Assign { name, value }, span);
+            }
+            ExprKind::Get {
+                object,
+                name,
+                optional: false,
+            } => ExprKind::Set {
+                object,
+                name,
+                operator,
+                value,
+            },
+            ExprKind::Index {
+                object,
+                bracket,
+                index,
+            } => ExprKind::IndexSet {
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+            },
+            ExprKind::List { elements } if operator.is_none() => {
+                match pattern(&self.exprs, &elements) {
+                    Some(pattern) => ExprKind::AssignPattern {
+                        pattern,
+                        equals: at.clone(),
+                        value,
+                    },
+                    None => {
+                        self.errors
+                            .push(self.error(at, "Invalid assignment target."));
+                        return target;
+                    }
+                }
+            }
+            _ => {
+                self.errors
+                    .push(self.error(at, "Invalid assignment target."));
+                return target;
+            }
+        };
+        self.exprs[target] = Expr::new(kind, span);
+        target
+    }
+
+    /// Parses a chain of calls, indexing and property accesses. A chain
+    /// with a `?.` link is wrapped in `OptionalChain`, which is also what
+    /// keeps it from being an assignment target.
+    fn call(&mut self) -> ParseResult<ExprId> {
+        let start = self.peek().span.start;
+        let mut expr = self.primary()?;
+        let mut optional = false;
+
+        loop {
+            if self.matches(&[TokenKind::LeftParen]) {
+                expr = self.finish_call(expr, false)?;
+            } else if self.matches(&[TokenKind::QuestionDot]) {
+                optional = true;
+                if self.matches(&[TokenKind::LeftParen]) {
+                    expr = self.finish_call(expr, true)?;
+                } else {
+                    let name = self.consume_identifier("Expect property name after '?.'.")?;
+                    let span = self.exprs[expr].span.start..name.span.end;
+                    let kind = ExprKind::Get {
+                        object: expr,
+                        name,
+                        optional: true,
+                    };
+                    expr = self.expr(kind, span);
+                }
+            } else if self.matches(&[TokenKind::LeftBracket]) {
+                let bracket = self.previous().clone();
+                let index = self.expression()?;
+                self.consume(&TokenKind::RightBracket, "Expect ']' after index.")?;
+                let span = self.exprs[expr].span.start..self.previous().span.end;
+                let kind = ExprKind::Index {
+                    object: expr,
+                    bracket,
+                    index,
+                };
+                expr = self.expr(kind, span);
+            } else if self.matches(&[TokenKind::Dot]) {
+                let name = self.consume_identifier("Expect property name after '.'.")?;
+                let span = self.exprs[expr].span.start..name.span.end;
+                let kind = ExprKind::Get {
+                    object: expr,
+                    name,
+                    optional: false,
+                };
+                expr = self.expr(kind, span);
+            } else {
+                break;
+            }
+        }
+
+        if optional {
+            let span = self.span_from(start);
+            return Ok(self.expr(ExprKind::OptionalChain(expr), span));
+        }
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: ExprId, optional: bool) -> ParseResult<ExprId> {
+        let mut arguments = vec![];
+        if !self.check(&TokenKind::RightParen) {
+            loop {
+                if arguments.len() == MAX_ARITY {
+                    let message = format!("Can't have more than {} arguments.", MAX_ARITY);
+                    self.errors.push(self.error(self.peek(), &message));
+                }
+                arguments.push(self.assignment()?);
+                if !self.matches(&[TokenKind::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self.consume(&TokenKind::RightParen, "Expect ')' after arguments.")?;
+        let span = self.exprs[callee].span.start..paren.span.end;
+        let kind = ExprKind::Call {
+            callee,
+            paren,
+            arguments,
+            optional,
+        };
+        Ok(self.expr(kind, span))
+    }
+
+    fn primary(&mut self) -> ParseResult<ExprId> {
+        use TokenKind::*;
+        let start = self.peek().span.start;
+        let kind = match self.peek().kind.clone() {
+            False => ExprKind::Literal(Literal::Bool(false)),
+            True => ExprKind::Literal(Literal::Bool(true)),
+            Nil => ExprKind::Literal(Literal::Nil),
+            Integer(n) => ExprKind::Literal(Literal::Number(n as f64)),
+            Float(n) => ExprKind::Literal(Literal::Number(n)),
+            String(s) => ExprKind::Literal(Literal::String(s)),
+            StringPart(s) => {
+                self.advance();
+                return self.interpolation(s);
+            }
+            Fun => {
+                let keyword = self.advance().clone();
+                // `fun name(...)` is a declaration where a statement starts
+                // and an error anywhere else, skipping the name so the rest
+                // parses as a lambda.
+                if let Identifier(_) = self.peek().kind {
+                    let message = "A function expression can't have a name.";
+                    self.errors.push(self.error(self.peek(), message));
+                    self.advance();
+                }
+                self.consume(&LeftParen, "Expect '(' after 'fun'.")?;
+                let function = self.function_body(keyword, start, "function")?;
+                let span = self.span_from(start);
+                return Ok(self.expr(ExprKind::Lambda(function), span));
+            }
+            This => ExprKind::This {
+                keyword: self.peek().clone(),
+            },
+            Super => {
+                let keyword = self.advance().clone();
+                self.consume(&Dot, "Expect '.' after 'super'.")?;
+                let method = self.consume_identifier("Expect superclass method name.")?;
+                let kind = ExprKind::Super { keyword, method };
+                let span = self.span_from(start);
+                return Ok(self.expr(kind, span));
+            }
+            Identifier(_) => ExprKind::Variable {
+                name: self.peek().clone(),
+            },
+            LeftParen => {
+                self.advance();
+                let expr = self.expression()?;
+                self.consume(&RightParen, "Expect ')' after expression.")?;
+                let span = self.span_from(start);
+                return Ok(self.expr(ExprKind::Grouping(expr), span));
+            }
+            LeftBracket => {
+                self.advance();
+                let elements = self.elements(&RightBracket)?;
+                self.consume(&RightBracket, "Expect ']' after list elements.")?;
+                let span = self.span_from(start);
+                return Ok(self.expr(ExprKind::List { elements }, span));
+            }
+            // Statements starting with `{` are blocks, so this is only
+            // reached where an expression is expected.
+            LeftBrace => {
+                let brace = self.advance().clone();
+                let entries = self.entries()?;
+                self.consume(&RightBrace, "Expect '}' after map entries.")?;
+                let span = self.span_from(start);
+                return Ok(self.expr(ExprKind::Map { brace, entries }, span));
+            }
+            _ => return Err(self.missing_left_operand()),
+        };
+
+        self.advance();
+        let span = self.span_from(start);
+        Ok(self.expr(kind, span))
+    }
+
+    /// Reports a binary operator found where an operand should start, as
+    /// in `+ 3`. The operator and its right operand are skipped so that
+    /// synchronization resumes after them.
+    fn missing_left_operand(&mut self) -> ParseError {
+        let rule = match infix_rule(&self.peek().kind) {
+            Some(rule) if rule.parse.is_binary() && self.peek().kind != TokenKind::Comma => rule,
+            _ => return self.error(self.peek(), "Expect expression."),
+        };
+
+        let operator = self.advance().clone();
+        let message = format!(
+            "Binary operator '{}' missing left-hand operand.",
+            operator.lexeme
+        );
+        let error = self.error(&operator, &message);
+        let _ = self.parse_precedence(rule.operand());
+        error
+    }
+
+    /// Comma-separated expressions up to, but not including, `close`. A
+    /// trailing comma is allowed.
+    fn elements(&mut self, close: &TokenKind) -> ParseResult<Vec<ExprId>> {
+        let mut elements = vec![];
+        while !self.check(close) {
+            elements.push(self.assignment()?);
+            if !self.matches(&[TokenKind::Comma]) {
+                break;
+            }
+        }
+        Ok(elements)
+    }
+
+    /// Comma-separated `key: value` pairs up to the closing `}`. A trailing
+    /// comma is allowed.
+    fn entries(&mut self) -> ParseResult<Vec<(ExprId, ExprId)>> {
+        let mut entries = vec![];
+        while !self.check(&TokenKind::RightBrace) {
+            let key = self.assignment()?;
+            self.consume(&TokenKind::Colon, "Expect ':' after map key.")?;
+            entries.push((key, self.assignment()?));
+            if !self.matches(&[TokenKind::Comma]) {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Parses the rest of an interpolated string after its first
+    /// `StringPart`: alternating expressions and further parts, up to the
+    /// closing `String`.
+    fn interpolation(&mut self, first: String) -> ParseResult<ExprId> {
+        let start = self.previous().span.start;
+        let mut parts = vec![];
+        let mut text = first;
+
+        loop {
+            if !text.is_empty() {
+                let span = self.previous().span.clone();
+                parts.push(self.expr(ExprKind::Literal(Literal::String(text)), span));
+            }
+            parts.push(self.expression()?);
+
+            match self.peek().kind.clone() {
+                TokenKind::StringPart(s) => {
+                    self.advance();
+                    text = s;
+                }
+                TokenKind::String(s) => {
+                    let span = self.advance().span.clone();
+                    if !s.is_empty() {
+                        parts.push(self.expr(ExprKind::Literal(Literal::String(s)), span));
+                    }
+                    let span = self.span_from(start);
+                    return Ok(self.expr(ExprKind::Interpolation { parts }, span));
+                }
+                _ => {
+                    let message = "Expect '}' after interpolated expression.";
+                    return Err(self.error(self.peek(), message));
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, kind: ExprKind, span: Range<usize>) -> ExprId {
+        self.exprs.alloc(Expr::new(kind, span))
+    }
+
+    /// The span from `start` to the end of the last consumed token.
+    fn span_from(&self, start: usize) -> Range<usize> {
+        start..self.previous().span.end
+    }
+
+    fn matches(&mut self, kinds: &[TokenKind]) -> bool {
+        if kinds.iter().any(|kind| self.check(kind)) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+
+    fn consume(&mut self, kind: &TokenKind, message: &str) -> ParseResult<Token> {
+        if self.check(kind) {
+            return Ok(self.advance().clone());
+        }
+        Err(self.error(self.peek(), message))
+    }
+
+    fn consume_identifier(&mut self, message: &str) -> ParseResult<Token> {
+        if let TokenKind::Identifier(_) = self.peek().kind {
+            return Ok(self.advance().clone());
+        }
+        Err(self.error(self.peek(), message))
+    }
+
+    fn check(&self, kind: &TokenKind) -> bool {
+        !self.is_at_end() && self.peek().kind == *kind
+    }
+
+    fn advance(&mut self) -> &Token {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        self.previous()
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek().kind == TokenKind::Eof
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current]
+    }
+
+    fn peek_next(&self) -> &Token {
+        self.tokens.get(self.current + 1).unwrap_or(self.peek())
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current - 1]
+    }
+
+    fn error(&self, token: &Token, message: &str) -> ParseError {
+        ParseError::new(token, message)
+    }
+}
+
+/// How tightly an operator binds, from loosest to tightest. Operators
+/// follow C precedence:
+///
+/// ```text
+/// Comma        ,                  (left)
+/// Assignment   = += -= *= /= %=   (right)
+/// Conditional  ?:                 (right)
+/// Coalesce     ??
+/// Or, And
+/// BitOr |, BitXor ^, BitAnd &
+/// Equality     == !=
+/// Comparison   < <= > >=
+/// Shift        << >>
+/// Term         + -
+/// Factor       * / %
+/// Unary        ! - ~ ++ --        (prefix)
+/// Power        **                 (right, so -2 ** 2 is -4)
+/// Postfix      ++ --
+/// ```
+///
+/// Calls, indexing and property accesses bind tighter still; they are
+/// parsed along with the primary expression they apply to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Comma,
+    Assignment,
+    Conditional,
+    Coalesce,
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Equality,
+    Comparison,
+    Shift,
+    Term,
+    Factor,
+    Unary,
+    Power,
+    Postfix,
+}
+
+impl Precedence {
+    /// The level above this one, for the right operand of a
+    /// left-associative operator.
+    fn next(self) -> Self {
+        use Precedence::*;
+        match self {
+            Comma => Assignment,
+            Assignment => Conditional,
+            Conditional => Coalesce,
+            Coalesce => Or,
+            Or => And,
+            And => BitOr,
+            BitOr => BitXor,
+            BitXor => BitAnd,
+            BitAnd => Equality,
+            Equality => Comparison,
+            Comparison => Shift,
+            Shift => Term,
+            Term => Factor,
+            Factor => Unary,
+            Unary => Power,
+            Power | Postfix => Postfix,
+        }
+    }
+}
+
+/// How the rest of an infix or postfix expression is parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Infix {
+    Binary,
+    /// Like `Binary`, but short-circuiting.
+    Logical,
+    /// Right-associative `**`, whose exponent may carry a prefix operator.
+    Power,
+    Assignment,
+    Conditional,
+    Postfix,
+}
+
+impl Infix {
+    /// Whether the operator takes a left and a right operand and nothing
+    /// else.
+    fn is_binary(self) -> bool {
+        matches!(self, Infix::Binary | Infix::Logical | Infix::Power)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct InfixRule {
+    precedence: Precedence,
+    parse: Infix,
+}
+
+impl InfixRule {
+    /// The precedence the operand after the operator is parsed at.
+    fn operand(self) -> Precedence {
+        match self.parse {
+            Infix::Binary | Infix::Logical => self.precedence.next(),
+            Infix::Power => Precedence::Unary,
+            Infix::Assignment | Infix::Conditional | Infix::Postfix => self.precedence,
+        }
+    }
+}
+
+/// The rule for a token that can follow a complete operand. A new binary
+/// operator needs only an entry here.
+fn infix_rule(kind: &TokenKind) -> Option<InfixRule> {
+    use TokenKind::*;
+    let (precedence, parse) = match kind {
+        Comma => (Precedence::Comma, Infix::Binary),
+        Equal | PlusEqual | MinusEqual | StarEqual | SlashEqual | PercentEqual => {
+            (Precedence::Assignment, Infix::Assignment)
+        }
+        Question => (Precedence::Conditional, Infix::Conditional),
+        QuestionQuestion => (Precedence::Coalesce, Infix::Logical),
+        Or => (Precedence::Or, Infix::Logical),
+        And => (Precedence::And, Infix::Logical),
+        Pipe => (Precedence::BitOr, Infix::Binary),
+        Caret => (Precedence::BitXor, Infix::Binary),
+        Ampersand => (Precedence::BitAnd, Infix::Binary),
+        BangEqual | EqualEqual => (Precedence::Equality, Infix::Binary),
+        Greater | GreaterEqual | Less | LessEqual => (Precedence::Comparison, Infix::Binary),
+        LessLess | GreaterGreater => (Precedence::Shift, Infix::Binary),
+        Minus | Plus => (Precedence::Term, Infix::Binary),
+        Slash | Star | Percent => (Precedence::Factor, Infix::Binary),
+        StarStar => (Precedence::Power, Infix::Power),
+        PlusPlus | MinusMinus => (Precedence::Postfix, Infix::Postfix),
+        _ => return None,
+    };
+    Some(InfixRule { precedence, parse })
+}
+
+/// The binary operator a compound assignment or `++`/`--` applies, as a
+/// token at the same position.
+fn compound_operator(token: &Token) -> Option<Token> {
+    use TokenKind::*;
+    let (kind, lexeme) = match token.kind {
+        PlusEqual | PlusPlus => (Plus, "+"),
+        MinusEqual | MinusMinus => (Minus, "-"),
+        StarEqual => (Star, "*"),
+        SlashEqual => (Slash, "/"),
+        PercentEqual => (Percent, "%"),
+        _ => return None,
+    };
+
+    let mut operator = token.clone();
+    operator.kind = kind;
+    operator.lexeme = lexeme.into();
+    Some(operator)
+}
+
+/// The pattern a list literal spells out, if its elements are all
+/// variables or nested lists of them.
+fn pattern(exprs: &Arena, elements: &[ExprId]) -> Option<Pattern> {
+    elements
+        .iter()
+        .map(|&element| match &exprs[element].kind {
+            ExprKind::Variable { name } => Some(Pattern::Name(name.clone())),
+            ExprKind::List { elements } => pattern(exprs, elements),
+            _ => None,
+        })
+        .collect::<Option<_>>()
+        .map(Pattern::List)
+}
+
+fn is_comment(kind: &TokenKind) -> bool {
+    use TokenKind::*;
+    matches!(kind, LineComment(_) | BlockComment(_) | DocComment(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn parse(source: &str) -> Result<Program, Vec<ParseError>> {
+        let (tokens, errors) = Scanner::new(source).scan_tokens();
+        assert!(errors.is_empty(), "{:?}", errors);
+        Parser::parse(tokens)
+    }
+
+    fn errors(source: &str) -> Vec<(String, String)> {
+        let errors = parse(source).expect_err("the source has errors");
+        errors
+            .into_iter()
+            .map(|error| {
+                (
+                    error.lexeme.as_deref().unwrap_or("").to_owned(),
+                    error.message,
+                )
+            })
+            .collect()
+    }
+
+    fn names(prefix: &str, count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("{}{}", prefix, i)).collect()
+    }
+
+    #[test]
+    fn the_most_parameters() {
+        let source = format!("fun f({}) {{}}", names("p", MAX_ARITY).join(", "));
+        let program = parse(&source).expect("255 parameters are allowed");
+        let StmtKind::Function(function) = &program.statements[0].kind else {
+            panic!("expected a function");
+        };
+        assert_eq!(function.params.len(), MAX_ARITY);
+    }
+
+    #[test]
+    fn too_many_parameters() {
+        // The error doesn't stop the rest of the file from parsing.
+        let source = format!(
+            "fun f({}) {{}}\nprint (;",
+            names("p", MAX_ARITY + 1).join(", ")
+        );
+        assert_eq!(
+            errors(&source),
+            [
+                ("p255".into(), "Can't have more than 255 parameters.".into()),
+                (";".into(), "Expect expression.".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_parameters_counts_the_rest_parameter() {
+        let source = format!("fun f({}, ...rest) {{}}", names("p", MAX_ARITY).join(", "));
+        assert_eq!(
+            errors(&source),
+            [("...".into(), "Can't have more than 255 parameters.".into())]
+        );
+    }
+
+    #[test]
+    fn the_most_arguments() {
+        let source = format!("f({});", names("a", MAX_ARITY).join(", "));
+        let program = parse(&source).expect("255 arguments are allowed");
+        let StmtKind::Expression(expr) = &program.statements[0].kind else {
+            panic!("expected an expression statement");
+        };
+        let ExprKind::Call { arguments, .. } = &program.exprs[*expr].kind else {
+            panic!("expected a call");
+        };
+        assert_eq!(arguments.len(), MAX_ARITY);
+    }
+
+    #[test]
+    fn too_many_arguments() {
+        let source = format!("f({});\nvar;", names("a", MAX_ARITY + 1).join(", "));
+        assert_eq!(
+            errors(&source),
+            [
+                ("a255".into(), "Can't have more than 255 arguments.".into()),
+                (";".into(), "Expect variable name.".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_the_rest_of_a_call_after_too_many_arguments() {
+        let source = format!("f({}, g(1));", names("a", MAX_ARITY).join(", "));
+        let errors = parse(&source).expect_err("256 arguments");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].lexeme.as_deref(), Some("g"));
+    }
+
+    #[test]
+    fn binary_operators_missing_a_left_operand() {
+        for operator in ["+", "*", "/", "==", "!=", "<", "<=", ">", ">="] {
+            let source = format!("{} 3;\nprint 1", operator);
+            let message = format!("Binary operator '{}' missing left-hand operand.", operator);
+            assert_eq!(
+                errors(&source),
+                [
+                    (operator.to_owned(), message),
+                    ("".into(), "Expect ';' after value.".into()),
+                ],
+                "{}",
+                operator
+            );
+        }
+    }
+
+    #[test]
+    fn unary_operators_are_not_missing_an_operand() {
+        for source in ["-3;", "!x;"] {
+            assert!(parse(source).is_ok(), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use crate::ast::{
+    Arena, Expr, ExprId, ExprKind, Function, Literal, Pattern, Program, Stmt, StmtKind,
+    TypeAnnotation,
+};
+use crate::token::Token;
+use crate::visit::Visitor;
+
+/// Renders the AST as parenthesized prefix expressions, e.g.
+/// `(* (- 123.0) (group 45.67))`, with one line per top-level statement.
+pub struct AstPrinter<'a> {
+    output: String,
+    exprs: &'a Arena,
+}
+
+impl<'a> AstPrinter<'a> {
+    pub fn print(program: &'a Program) -> String {
+        let mut printer = Self {
+            output: String::new(),
+            exprs: &program.exprs,
+        };
+        for statement in &program.statements {
+            printer.visit_stmt(statement);
+            printer.output.push('\n');
+        }
+        printer.output
+    }
+
+    pub fn print_expr(exprs: &'a Arena, expr: ExprId) -> String {
+        let mut printer = Self {
+            output: String::new(),
+            exprs,
+        };
+        printer.visit_expr(&exprs[expr]);
+        printer.output
+    }
+
+    fn function(&mut self, keyword: &str, function: &Function) {
+        self.open(keyword);
+        self.atom(&function.name.lexeme);
+        self.parameters(function);
+        self.statements(&function.body);
+        self.close();
+    }
+
+    fn parameters(&mut self, function: &Function) {
+        self.output.push_str(" (");
+        for (i, param) in function.params.iter().enumerate() {
+            if i > 0 {
+                self.output.push(' ');
+            }
+            match &param.default {
+                Some(default) => {
+                    self.open("=");
+                    self.atom(&param.name.lexeme);
+                    self.annotation(param.annotation.as_ref());
+                    self.child(*default);
+                    self.close();
+                }
+                None => {
+                    self.output.push_str(&param.name.lexeme);
+                    self.annotation(param.annotation.as_ref());
+                }
+            }
+        }
+        if let Some(rest) = &function.rest {
+            if !function.params.is_empty() {
+                self.output.push(' ');
+            }
+            self.output.push_str("...");
+            self.output.push_str(&rest.lexeme);
+        }
+        self.output.push(')');
+        self.annotation(function.return_type.as_ref());
+    }
+
+    /// Prints `:Type` straight after the annotated name.
+    fn annotation(&mut self, annotation: Option<&TypeAnnotation>) {
+        if let Some(annotation) = annotation {
+            self.output.push(':');
+            self.output.push_str(&annotation.name.lexeme);
+        }
+    }
+
+    /// Prints a pattern as ` [a [b c]]`.
+    fn pattern(&mut self, pattern: &Pattern) {
+        self.output.push(' ');
+        self.pattern_elements(pattern);
+    }
+
+    fn pattern_elements(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Name(name) => self.output.push_str(&name.lexeme),
+            Pattern::List(elements) => {
+                self.output.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push(' ');
+                    }
+                    self.pattern_elements(element);
+                }
+                self.output.push(']');
+            }
+        }
+    }
+
+    fn assignment_operator(&mut self, operator: Option<&Token>) {
+        match operator {
+            Some(operator) => self.open(&format!("{}=", operator.lexeme)),
+            None => self.open("="),
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            Literal::Nil => self.output.push_str("nil"),
+            Literal::Bool(b) => self.output.push_str(&b.to_string()),
+            Literal::Number(n) => self.output.push_str(&format!("{:?}", n)),
+            Literal::String(s) => self.output.push_str(&format!("\"{}\"", s.escape_debug())),
+        }
+    }
+
+    fn parenthesize(&mut self, name: &str, exprs: &[ExprId]) {
+        self.open(name);
+        exprs.iter().for_each(|&expr| self.child(expr));
+        self.close();
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) {
+        statements
+            .iter()
+            .for_each(|statement| self.child_stmt(statement));
+    }
+
+    fn open(&mut self, name: &str) {
+        self.output.push('(');
+        self.output.push_str(name);
+    }
+
+    fn close(&mut self) {
+        self.output.push(')');
+    }
+
+    fn atom(&mut self, text: &str) {
+        self.output.push(' ');
+        self.output.push_str(text);
+    }
+
+    fn child(&mut self, expr: ExprId) {
+        self.output.push(' ');
+        let exprs = self.exprs;
+        self.visit_expr(&exprs[expr]);
+    }
+
+    fn child_stmt(&mut self, stmt: &Stmt) {
+        self.output.push(' ');
+        self.visit_stmt(stmt);
+    }
+}
+
+impl<'a> Visitor<'a> for AstPrinter<'a> {
+    fn exprs(&self) -> &'a Arena {
+        self.exprs
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Literal(literal) => self.literal(literal),
+            ExprKind::Grouping(expr) => self.parenthesize("group", &[*expr]),
+            ExprKind::Variable { name } => self.output.push_str(&name.lexeme),
+            ExprKind::Assign { name, value } => {
+                self.open("=");
+                self.atom(&name.lexeme);
+                self.child(*value);
+                self.close();
+            }
+            ExprKind::Unary { operator, right } => self.parenthesize(&operator.lexeme, &[*right]),
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            }
+            | ExprKind::Logical {
+                left,
+                operator,
+                right,
+            } => self.parenthesize(&operator.lexeme, &[*left, *right]),
+            ExprKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => self.parenthesize("?:", &[*condition, *then_branch, *else_branch]),
+            ExprKind::Call {
+                callee,
+                arguments,
+                optional,
+                ..
+            } => {
+                self.open(if *optional { "?.call" } else { "call" });
+                self.child(*callee);
+                arguments.iter().for_each(|&argument| self.child(argument));
+                self.close();
+            }
+            ExprKind::Get {
+                object,
+                name,
+                optional,
+            } => {
+                self.open(if *optional { "?." } else { "." });
+                self.child(*object);
+                self.atom(&name.lexeme);
+                self.close();
+            }
+            ExprKind::Set {
+                object,
+                name,
+                operator,
+                value,
+            } => {
+                self.assignment_operator(operator.as_ref());
+                self.output.push(' ');
+                self.open(".");
+                self.child(*object);
+                self.atom(&name.lexeme);
+                self.close();
+                self.child(*value);
+                self.close();
+            }
+            ExprKind::AssignPattern { pattern, value, .. } => {
+                self.open("=");
+                self.pattern(pattern);
+                self.child(*value);
+                self.close();
+            }
+            ExprKind::Postfix { target, operator } => {
+                self.parenthesize(&format!("post{}", operator.lexeme), &[*target])
+            }
+            ExprKind::OptionalChain(expr) => self.parenthesize("chain", &[*expr]),
+            ExprKind::List { elements } => {
+                self.open("list");
+                elements.iter().for_each(|&element| self.child(element));
+                self.close();
+            }
+            ExprKind::Map { entries, .. } => {
+                self.open("map");
+                for (key, value) in entries {
+                    self.output.push(' ');
+                    self.parenthesize(":", &[*key, *value]);
+                }
+                self.close();
+            }
+            ExprKind::Index { object, index, .. } => self.parenthesize("index", &[*object, *index]),
+            ExprKind::IndexSet {
+                object,
+                index,
+                operator,
+                value,
+                ..
+            } => {
+                self.assignment_operator(operator.as_ref());
+                self.output.push(' ');
+                self.parenthesize("index", &[*object, *index]);
+                self.child(*value);
+                self.close();
+            }
+            ExprKind::This { .. } => self.output.push_str("this"),
+            ExprKind::Super { method, .. } => {
+                self.open("super");
+                self.atom(&method.lexeme);
+                self.close();
+            }
+            ExprKind::Lambda(function) => {
+                self.open("lambda");
+                self.parameters(function);
+                self.statements(&function.body);
+                self.close();
+            }
+            ExprKind::Interpolation { parts } => {
+                self.open("interpolate");
+                parts.iter().for_each(|&part| self.child(part));
+                self.close();
+            }
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Expression(expr) => self.parenthesize("expr", &[*expr]),
+            StmtKind::Print(expr) => self.parenthesize("print", &[*expr]),
+            StmtKind::Var {
+                name,
+                annotation,
+                initializer,
+                constant,
+            } => {
+                self.open(if *constant { "const" } else { "var" });
+                self.atom(&name.lexeme);
+                self.annotation(annotation.as_ref());
+                if let Some(initializer) = initializer {
+                    self.child(*initializer);
+                }
+                self.close();
+            }
+            StmtKind::VarPattern {
+                pattern,
+                initializer,
+                constant,
+                ..
+            } => {
+                self.open(if *constant { "const" } else { "var" });
+                self.pattern(pattern);
+                self.child(*initializer);
+                self.close();
+            }
+            StmtKind::Block(statements) => {
+                self.open("block");
+                self.statements(statements);
+                self.close();
+            }
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.open("if");
+                self.child(*condition);
+                self.child_stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.child_stmt(else_branch);
+                }
+                self.close();
+            }
+            StmtKind::While {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
+                self.open("while");
+                self.child(*condition);
+                self.child_stmt(body);
+                if let Some(increment) = increment {
+                    self.child(*increment);
+                }
+                self.close();
+            }
+            StmtKind::ForIn {
+                name,
+                iterable,
+                body,
+            } => {
+                self.open("for-in");
+                self.atom(&name.lexeme);
+                self.child(*iterable);
+                self.child_stmt(body);
+                self.close();
+            }
+            StmtKind::Break { .. } => self.output.push_str("(break)"),
+            StmtKind::Continue { .. } => self.output.push_str("(continue)"),
+            StmtKind::Switch {
+                subject,
+                cases,
+                default,
+                ..
+            } => {
+                self.open("switch");
+                self.child(*subject);
+                for case in cases {
+                    self.output.push(' ');
+                    self.open("case");
+                    self.child(case.value);
+                    self.statements(&case.body);
+                    self.close();
+                }
+                if let Some(default) = default {
+                    self.output.push(' ');
+                    self.open("default");
+                    self.statements(default);
+                    self.close();
+                }
+                self.close();
+            }
+            StmtKind::Function(function) => self.function("fun", function),
+            StmtKind::Return { value, .. } => {
+                self.open("return");
+                if let Some(value) = value {
+                    self.child(*value);
+                }
+                self.close();
+            }
+            StmtKind::Import { path, alias, .. } => {
+                self.open("import");
+                self.atom(&format!("\"{}\"", path.escape_debug()));
+                if let Some(alias) = alias {
+                    self.atom("as");
+                    self.atom(&alias.lexeme);
+                }
+                self.close();
+            }
+            StmtKind::Throw { value, .. } => self.parenthesize("throw", &[*value]),
+            StmtKind::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.open("try");
+                self.statements(body);
+                if let Some(catch) = catch {
+                    self.output.push(' ');
+                    self.open("catch");
+                    self.atom(&catch.name.lexeme);
+                    self.statements(&catch.body);
+                    self.close();
+                }
+                if let Some(finally) = finally {
+                    self.output.push(' ');
+                    self.open("finally");
+                    self.statements(finally);
+                    self.close();
+                }
+                self.close();
+            }
+            StmtKind::Class {
+                name,
+                superclass,
+                fields,
+                methods,
+                static_methods,
+            } => {
+                self.open("class");
+                self.atom(&name.lexeme);
+                if let Some(superclass) = superclass {
+                    self.atom("<");
+                    self.child(*superclass);
+                }
+                self.statements(fields);
+                for method in methods {
+                    self.output.push(' ');
+                    self.function("fun", method);
+                }
+                for method in static_methods {
+                    self.output.push(' ');
+                    self.function("static", method);
+                }
+                self.close();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenKind;
+    use std::rc::Rc;
+
+    fn expr(exprs: &mut Arena, kind: ExprKind) -> ExprId {
+        exprs.alloc(Expr::new(kind, 0..0))
+    }
+
+    fn number(exprs: &mut Arena, n: f64) -> ExprId {
+        expr(exprs, ExprKind::Literal(Literal::Number(n)))
+    }
+
+    fn operator(kind: TokenKind, lexeme: &str) -> Token {
+        Token::new(kind, lexeme.into(), 1, 1, 0..lexeme.len())
+    }
+
+    #[test]
+    fn prints_a_hand_built_expression() {
+        let mut exprs = Arena::default();
+        let right = number(&mut exprs, 123.0);
+        let left = expr(
+            &mut exprs,
+            ExprKind::Unary {
+                operator: operator(TokenKind::Minus, "-"),
+                right,
+            },
+        );
+        let grouped = number(&mut exprs, 45.67);
+        let right = expr(&mut exprs, ExprKind::Grouping(grouped));
+        let tree = expr(
+            &mut exprs,
+            ExprKind::Binary {
+                left,
+                operator: operator(TokenKind::Star, "*"),
+                right,
+            },
+        );
+        assert_eq!(
+            AstPrinter::print_expr(&exprs, tree),
+            "(* (- 123.0) (group 45.67))"
+        );
+    }
+
+    #[test]
+    fn prints_one_line_per_statement() {
+        let mut exprs = Arena::default();
+        let print = |value: ExprId| Stmt::new(StmtKind::Print(value), 0..0);
+        let statements = vec![
+            print(number(&mut exprs, 1.0)),
+            Stmt::new(StmtKind::Block(vec![print(number(&mut exprs, 2.0))]), 0..0),
+        ];
+        let program = Program {
+            exprs: Rc::new(exprs),
+            statements,
+        };
+        assert_eq!(
+            AstPrinter::print(&program),
+            "(print 1.0)\n(block (print 2.0))\n"
+        );
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{Arena, Expr, ExprId, ExprKind, Function, Pattern, Program, Stmt, StmtKind};
+use crate::error::{ResolveError, Severity};
+use crate::token::Token;
+use crate::visit::{walk_expr, walk_stmt, walk_stmts, Visitor};
+
+/// Where each local variable reference is declared, as a number of scopes
+/// out from the reference. References are keyed by their name token's
+/// offset in the source; names that aren't here are globals.
+#[derive(Debug, Clone, Default)]
+pub struct Locals {
+    depths: HashMap<usize, usize>,
+}
+
+impl Locals {
+    pub fn depth(&self, name: &Token) -> Option<usize> {
+        self.depths.get(&name.span.start).copied()
+    }
+}
+
+/// A name declared in a local scope.
+struct Binding {
+    /// False while the declaration's initializer is being resolved.
+    defined: bool,
+    /// The line of a `const` declaration.
+    constant: Option<usize>,
+    /// The declaration, until the name is first read. Parameters, names
+    /// starting with `_` and names the interpreter binds itself are never
+    /// reported as unused.
+    unread: Option<Token>,
+}
+
+/// The kind of class declaration being resolved, if any.
+#[derive(Clone, Copy)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
+    /// A static method of either, which has no instance to use.
+    Static,
+}
+
+/// The kind of function whose body is being resolved, if any.
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionKind {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+/// Resolves each local variable reference to the scope it's declared in,
+/// mirroring the scopes the interpreter creates, and reports the misuses
+/// of names that can be found before running. It also warns about locals
+/// that are never read; assigning to one, with `+=` or `++` too, doesn't
+/// count.
+pub struct Resolver<'a> {
+    exprs: &'a Arena,
+    scopes: Vec<HashMap<Rc<str>, Binding>>,
+    /// Top-level constants declared so far, with their lines.
+    global_constants: HashMap<Rc<str>, usize>,
+    class: ClassKind,
+    function: FunctionKind,
+    locals: Locals,
+    errors: Vec<ResolveError>,
+}
+
+impl<'a> Resolver<'a> {
+    /// The locals and any warnings, or else the errors and warnings
+    /// together. Either list is in source order.
+    pub fn resolve(program: &'a Program) -> Result<(Locals, Vec<ResolveError>), Vec<ResolveError>> {
+        let mut resolver = Resolver {
+            exprs: &program.exprs,
+            scopes: vec![],
+            global_constants: HashMap::new(),
+            class: ClassKind::None,
+            function: FunctionKind::None,
+            locals: Locals::default(),
+            errors: vec![],
+        };
+        walk_stmts(&mut resolver, &program.statements);
+        let mut diagnostics = resolver.errors;
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+        if diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            Err(diagnostics)
+        } else {
+            Ok((resolver.locals, diagnostics))
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: ExprId) {
+        let exprs = self.exprs;
+        self.visit_expr(&exprs[expr]);
+    }
+
+    fn scoped(&mut self, resolve: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        resolve(self);
+        let scope = self.scopes.pop().expect("pushed above");
+        for name in scope.into_values().filter_map(|binding| binding.unread) {
+            let message = format!("local variable '{}' is never used.", name.lexeme);
+            self.errors.push(ResolveError::warning(&name, message));
+        }
+    }
+
+    /// Adds `name` to the innermost scope, not yet readable. Only globals
+    /// can be declared again.
+    fn declare(&mut self, name: &Token, constant: bool) {
+        let constant = constant.then_some(name.line);
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                if scope.contains_key(&name.lexeme) {
+                    self.errors.push(ResolveError::new(
+                        name,
+                        "Already a variable with this name in this scope.",
+                    ));
+                }
+                let binding = Binding {
+                    defined: false,
+                    constant,
+                    unread: (!name.lexeme.starts_with('_')).then(|| name.clone()),
+                };
+                scope.insert(name.lexeme.clone(), binding);
+            }
+            None => match constant {
+                Some(line) => {
+                    self.global_constants.insert(name.lexeme.clone(), line);
+                }
+                None => {
+                    self.global_constants.remove(&name.lexeme);
+                }
+            },
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(binding) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.get_mut(&name.lexeme))
+        {
+            binding.defined = true;
+        }
+    }
+
+    /// Declares and defines a parameter, which can go unused.
+    fn declare_parameter(&mut self, name: &Token) {
+        self.declare(name, false);
+        self.define(name);
+        self.mark_read(name);
+    }
+
+    /// Adds a name the interpreter binds itself, like `this`, to the
+    /// innermost scope.
+    fn declare_implicit(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            let binding = Binding {
+                defined: true,
+                constant: None,
+                unread: None,
+            };
+            scope.insert(name.into(), binding);
+        }
+    }
+
+    /// Methods and field initializers see `this` in a scope of its own
+    /// around them; static methods can't use `this` or `super`.
+    fn class_body(
+        &mut self,
+        fields: &[Stmt],
+        methods: &[Rc<Function>],
+        static_methods: &[Rc<Function>],
+    ) {
+        self.scoped(|resolver| {
+            resolver.declare_implicit("this");
+            for field in fields {
+                match &field.kind {
+                    StmtKind::Var {
+                        initializer: Some(initializer),
+                        ..
+                    }
+                    | StmtKind::VarPattern { initializer, .. } => {
+                        resolver.resolve_expr(*initializer)
+                    }
+                    _ => {}
+                }
+            }
+            for method in methods {
+                let kind = match &*method.name.lexeme {
+                    "init" => FunctionKind::Initializer,
+                    _ => FunctionKind::Method,
+                };
+                resolver.resolve_function(method, kind);
+            }
+        });
+        let class = std::mem::replace(&mut self.class, ClassKind::Static);
+        for method in static_methods {
+            self.resolve_function(method, FunctionKind::Method);
+        }
+        self.class = class;
+    }
+
+    /// Each default is evaluated in the scope of the parameters, so it
+    /// resolves after the ones before it are declared.
+    fn resolve_function(&mut self, function: &Function, kind: FunctionKind) {
+        let enclosing = std::mem::replace(&mut self.function, kind);
+        self.scoped(|resolver| {
+            for param in &function.params {
+                if let Some(default) = &param.default {
+                    resolver.resolve_expr(*default);
+                }
+                resolver.declare_parameter(&param.name);
+            }
+            if let Some(rest) = &function.rest {
+                resolver.declare_parameter(rest);
+            }
+            walk_stmts(resolver, &function.body);
+        });
+        self.function = enclosing;
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern, constant: bool) {
+        match pattern {
+            Pattern::Name(name) => self.declare(name, constant),
+            Pattern::List(patterns) => patterns
+                .iter()
+                .for_each(|pattern| self.declare_pattern(pattern, constant)),
+        }
+    }
+
+    fn define_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Name(name) => self.define(name),
+            Pattern::List(patterns) => patterns
+                .iter()
+                .for_each(|pattern| self.define_pattern(pattern)),
+        }
+    }
+
+    /// Reports a use of `name` in the initializer of its own declaration.
+    fn check_defined(&mut self, name: &Token) {
+        let declaring = self
+            .scopes
+            .last()
+            .and_then(|scope| scope.get(&name.lexeme))
+            .is_some_and(|binding| !binding.defined);
+        if declaring {
+            self.errors.push(ResolveError::new(
+                name,
+                "Can't read local variable in its own initializer.",
+            ));
+        }
+    }
+
+    /// Marks the nearest declaration of `name` as used.
+    fn mark_read(&mut self, name: &Token) {
+        if let Some(binding) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(&name.lexeme))
+        {
+            binding.unread = None;
+        }
+    }
+
+    fn resolve_local(&mut self, name: &Token) {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(&name.lexeme));
+        if let Some(depth) = found {
+            self.locals.depths.insert(name.span.start, depth);
+        }
+    }
+
+    /// Resolves a name being assigned to, which can't be a constant.
+    fn resolve_assignment(&mut self, name: &Token) {
+        let constant = match self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme))
+        {
+            Some(binding) => binding.constant,
+            None => self.global_constants.get(&name.lexeme).copied(),
+        };
+        if let Some(line) = constant {
+            let message = format!(
+                "Can't assign to constant '{}' declared on line {}.",
+                name.lexeme, line
+            );
+            self.errors.push(ResolveError::new(name, message));
+        }
+        self.resolve_local(name);
+    }
+
+    fn resolve_pattern_assignment(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Name(name) => self.resolve_assignment(name),
+            Pattern::List(patterns) => patterns
+                .iter()
+                .for_each(|pattern| self.resolve_pattern_assignment(pattern)),
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Resolver<'a> {
+    fn exprs(&self) -> &'a Arena {
+        self.exprs
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Variable { name } => {
+                self.check_defined(name);
+                self.mark_read(name);
+                self.resolve_local(name);
+            }
+            ExprKind::Assign { name, value } => {
+                // `a += b` reads `a` only to write it back, so like `a++` it
+                // doesn't count as a use.
+                match compound_operand(self.exprs, name, *value) {
+                    Some(operand) => {
+                        self.check_defined(name);
+                        self.resolve_expr(operand);
+                    }
+                    None => self.resolve_expr(*value),
+                }
+                self.resolve_assignment(name);
+            }
+            ExprKind::AssignPattern { pattern, value, .. } => {
+                self.resolve_expr(*value);
+                self.resolve_pattern_assignment(pattern);
+            }
+            ExprKind::Postfix { target, .. } => match &self.exprs[*target].kind {
+                ExprKind::Variable { name } => {
+                    self.check_defined(name);
+                    self.resolve_assignment(name);
+                }
+                _ => self.resolve_expr(*target),
+            },
+            ExprKind::This { keyword } => match self.class {
+                ClassKind::None => self.errors.push(ResolveError::new(
+                    keyword,
+                    "Can't use 'this' outside of a class.",
+                )),
+                ClassKind::Static => self.errors.push(ResolveError::new(
+                    keyword,
+                    "Can't use 'this' in a static method.",
+                )),
+                _ => self.resolve_local(keyword),
+            },
+            ExprKind::Super { keyword, .. } => match self.class {
+                ClassKind::None => self.errors.push(ResolveError::new(
+                    keyword,
+                    "Can't use 'super' outside of a class.",
+                )),
+                ClassKind::Class => self.errors.push(ResolveError::new(
+                    keyword,
+                    "Can't use 'super' in a class with no superclass.",
+                )),
+                ClassKind::Static => self.errors.push(ResolveError::new(
+                    keyword,
+                    "Can't use 'super' in a static method.",
+                )),
+                ClassKind::Subclass => self.resolve_local(keyword),
+            },
+            _ => walk_expr(self, expr),
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Block(statements) => self.scoped(|resolver| walk_stmts(resolver, statements)),
+            StmtKind::Var {
+                name,
+                initializer,
+                constant,
+                ..
+            } => {
+                self.declare(name, *constant);
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(*initializer);
+                }
+                self.define(name);
+            }
+            StmtKind::VarPattern {
+                pattern,
+                initializer,
+                constant,
+                ..
+            } => {
+                self.declare_pattern(pattern, *constant);
+                self.resolve_expr(*initializer);
+                self.define_pattern(pattern);
+            }
+            StmtKind::Function(function) => {
+                self.declare(&function.name, false);
+                self.define(&function.name);
+                self.visit_function(function);
+            }
+            StmtKind::ForIn {
+                name,
+                iterable,
+                body,
+            } => {
+                self.resolve_expr(*iterable);
+                self.scoped(|resolver| {
+                    resolver.declare(name, false);
+                    resolver.define(name);
+                    resolver.visit_stmt(body);
+                });
+            }
+            StmtKind::Switch {
+                subject,
+                cases,
+                default,
+                ..
+            } => {
+                self.resolve_expr(*subject);
+                for case in cases {
+                    self.resolve_expr(case.value);
+                    self.scoped(|resolver| walk_stmts(resolver, &case.body));
+                }
+                if let Some(default) = default {
+                    self.scoped(|resolver| walk_stmts(resolver, default));
+                }
+            }
+            StmtKind::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.scoped(|resolver| walk_stmts(resolver, body));
+                if let Some(catch) = catch {
+                    self.scoped(|resolver| {
+                        resolver.declare_parameter(&catch.name);
+                        walk_stmts(resolver, &catch.body);
+                    });
+                }
+                if let Some(finally) = finally {
+                    self.scoped(|resolver| walk_stmts(resolver, finally));
+                }
+            }
+            StmtKind::Class {
+                name,
+                superclass,
+                fields,
+                methods,
+                static_methods,
+            } => {
+                self.declare(name, false);
+                self.define(name);
+                let enclosing = std::mem::replace(&mut self.class, ClassKind::Class);
+                match superclass {
+                    Some(superclass) => {
+                        if let ExprKind::Variable { name: parent } = &self.exprs[*superclass].kind {
+                            if parent.lexeme == name.lexeme {
+                                self.errors.push(ResolveError::new(
+                                    parent,
+                                    "A class can't inherit from itself.",
+                                ));
+                            }
+                        }
+                        self.resolve_expr(*superclass);
+                        self.class = ClassKind::Subclass;
+                        // `super` is bound in a scope around the whole body.
+                        self.scoped(|resolver| {
+                            resolver.declare_implicit("super");
+                            resolver.class_body(fields, methods, static_methods);
+                        });
+                    }
+                    None => self.class_body(fields, methods, static_methods),
+                }
+                self.class = enclosing;
+            }
+            StmtKind::Return { keyword, value } => {
+                let message = match (self.function, value) {
+                    (FunctionKind::None, _) => Some("Can't return from top-level code."),
+                    (FunctionKind::Initializer, Some(_)) => {
+                        Some("Can't return a value from an initializer.")
+                    }
+                    _ => None,
+                };
+                if let Some(message) = message {
+                    self.errors.push(ResolveError::new(keyword, message));
+                }
+                if let Some(value) = value {
+                    self.resolve_expr(*value);
+                }
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.resolve_function(function, FunctionKind::Function);
+    }
+}
+
+/// The right operand of `name op= operand`, which the parser turns into
+/// `name = name op operand`.
+fn compound_operand(exprs: &Arena, name: &Token, value: ExprId) -> Option<ExprId> {
+    match &exprs[value].kind {
+        ExprKind::Binary { left, right, .. } => match &exprs[*left].kind {
+            ExprKind::Variable { name: read } if read.span == name.span => Some(*right),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -1,124 +1,479 @@
-use crate::token::{Token, TokenKind};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::{ScanError, ScanErrorKind};
+use crate::token::{Token, TokenKind, Trivia};
+
+#[rustfmt::skip]
+const KEYWORDS: &[(&str, TokenKind)] = {
+    use TokenKind::*;
+    &[
+        ("and", And), ("as", As), ("break", Break), ("case", Case),
+        ("catch", Catch), ("class", Class), ("const", Const),
+        ("continue", Continue), ("default", Default), ("else", Else),
+        ("false", False), ("finally", Finally), ("for", For), ("fun", Fun),
+        ("if", If), ("import", Import), ("in", In), ("nil", Nil), ("or", Or),
+        ("print", Print), ("return", Return), ("static", Static),
+        ("super", Super), ("switch", Switch), ("this", This),
+        ("throw", Throw), ("true", True), ("try", Try), ("var", Var),
+        ("while", While),
+    ]
+};
+
+/// Dialect settings for a `Scanner`. The default matches standard Lox.
+#[derive(Debug, Clone)]
+pub struct ScannerConfig {
+    pub keywords: HashMap<String, TokenKind>,
+    pub limits: ScannerLimits,
+}
+
+/// Bounds that stop pathological input from exhausting the host. Hitting
+/// one produces a `LimitExceeded` error.
+#[derive(Debug, Clone, Copy)]
+pub struct ScannerLimits {
+    /// Longest string literal value, in bytes.
+    pub max_string_len: usize,
+    /// Deepest nesting of block comments or string interpolations.
+    pub max_nesting: usize,
+    /// Most tokens scanned before the rest of the input is abandoned.
+    pub max_tokens: usize,
+}
+
+impl Default for ScannerLimits {
+    fn default() -> Self {
+        Self {
+            max_string_len: 1 << 24,
+            max_nesting: 256,
+            max_tokens: 1 << 24,
+        }
+    }
+}
+
+impl ScannerConfig {
+    /// Reserves `name` as a keyword that scans as `TokenKind::Custom(name)`.
+    pub fn add_keyword(&mut self, name: &str) {
+        self.keywords
+            .insert(name.to_owned(), TokenKind::Custom(name.to_owned()));
+    }
+
+    /// Makes `name` scan as a plain identifier.
+    pub fn remove_keyword(&mut self, name: &str) {
+        self.keywords.remove(name);
+    }
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        let keywords = KEYWORDS
+            .iter()
+            .map(|(name, kind)| (name.to_string(), kind.clone()))
+            .collect();
+        Self {
+            keywords,
+            limits: ScannerLimits::default(),
+        }
+    }
+}
+
+/// An open `${` inside a string literal, waiting for its closing brace.
+struct Interpolation {
+    depth: usize,
+    line: usize,
+    column: usize,
+    offset: usize,
+}
 
 pub struct Scanner<'a> {
     source: &'a str,
-    tokens: Vec<Token>,
-    stream: std::iter::Peekable<std::str::Chars<'a>>,
+    config: ScannerConfig,
+    finished: bool,
+    trivia: bool,
+    lossless: bool,
+    leading_trivia: Vec<Trivia>,
+    interpolations: Vec<Interpolation>,
+    // One shared allocation per distinct lexeme, keyed by source slice.
+    interner: HashMap<&'a str, Rc<str>>,
+    tokens: usize,
+    // Whether the last token could end an operand, which is what decides
+    // if a following `.5` is a number or a property access.
+    after_operand: bool,
 
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    column: usize,
+
+    start_line: usize,
+    start_line_start: usize,
+    start_column: usize,
 }
 
 impl<'a> Scanner<'a> {
     pub fn new(source: &'a str) -> Self {
-        let stream = source.chars().peekable();
+        Self::new_with_config(source, ScannerConfig::default())
+    }
+
+    pub fn new_with_limits(source: &'a str, limits: ScannerLimits) -> Self {
+        let config = ScannerConfig {
+            limits,
+            ..ScannerConfig::default()
+        };
+        Self::new_with_config(source, config)
+    }
 
+    pub fn new_with_config(source: &'a str, config: ScannerConfig) -> Self {
         Self {
             source,
-            tokens: vec![],
-            stream,
+            config,
+            finished: false,
+            trivia: false,
+            lossless: false,
+            leading_trivia: vec![],
+            interpolations: vec![],
+            interner: HashMap::new(),
+            tokens: 0,
+            after_operand: false,
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            column: 1,
+            start_line: 1,
+            start_line_start: 0,
+            start_column: 1,
         }
     }
 
-    pub fn scan_tokens(mut self) -> Vec<Token> {
-        while let Some(c) = self.next() {
-            self.start = self.current - 1;
+    /// When enabled, comments are emitted as `LineComment`, `DocComment`
+    /// and `BlockComment` tokens instead of being skipped.
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
 
-            use TokenKind::*;
-            match c {
-                '(' => self.add_token(LeftParen),
-                ')' => self.add_token(RightParen),
-                '{' => self.add_token(LeftBrace),
-                '}' => self.add_token(RightBrace),
-                ',' => self.add_token(Comma),
-                '.' => self.add_token(Dot),
-                '-' => self.add_token(Minus),
-                '+' => self.add_token(Plus),
-                ';' => self.add_token(Semicolon),
-                '*' => self.add_token(Star),
-                '!' => {
-                    let tok = if self.expect_next('=') {
-                        BangEqual
-                    } else {
-                        Bang
-                    };
-                    self.add_token(tok);
+    /// When enabled, every skipped whitespace run, newline and comment is
+    /// attached to the following token as leading trivia, so concatenating
+    /// trivia and lexemes reproduces the source exactly.
+    pub fn with_lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+
+    pub fn scan_tokens(self) -> (Vec<Token>, Vec<ScanError>) {
+        let mut tokens = vec![];
+        let mut errors = vec![];
+
+        for result in self {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        (tokens, errors)
+    }
+
+    /// Scans a single lexeme starting with `c`. Returns `None` for trivia
+    /// such as whitespace and comments.
+    fn scan_token(&mut self, c: char) -> Option<Result<TokenKind, ScanError>> {
+        use TokenKind::*;
+        let tok = match c {
+            '(' => LeftParen,
+            ')' => RightParen,
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.depth += 1;
                 }
-                '=' => {
-                    let tok = if self.expect_next('=') {
-                        EqualEqual
-                    } else {
-                        Equal
-                    };
-                    self.add_token(tok);
+                LeftBrace
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(interpolation) if interpolation.depth == 0 => {
+                    self.interpolations.pop();
+                    return Some(self.scan_string());
                 }
-                '<' => {
-                    let tok = if self.expect_next('=') {
-                        LessEqual
-                    } else {
-                        Less
-                    };
-                    self.add_token(tok);
+                Some(interpolation) => {
+                    interpolation.depth -= 1;
+                    RightBrace
                 }
-                '>' => {
-                    let tok = if self.expect_next('=') {
-                        GreaterEqual
-                    } else {
-                        Greater
-                    };
-                    self.add_token(tok);
+                None => RightBrace,
+            },
+            '[' => LeftBracket,
+            ']' => RightBracket,
+            ':' => Colon,
+            // `a ?.5 : b` is a conditional, not `?.` and `5`.
+            '?' => {
+                if self.expect_next('?') {
+                    QuestionQuestion
+                } else if self.peek() == Some('.') && !matches!(self.peek_nth(1), Some('0'..='9')) {
+                    self.advance();
+                    QuestionDot
+                } else {
+                    Question
                 }
-                '/' => {
-                    if self.expect_next('/') {
-                        while let Some(c) = self.peek() {
-                            if c == '\n' {
-                                break;
-                            }
-                            self.next();
-                        }
-                    } else if self.expect_next('*') {
-                        self.scan_multiline_comment()
-                    } else {
-                        self.add_token(Slash);
+            }
+            ',' => Comma,
+            // `.5` is a number unless it directly follows an operand, so
+            // `obj.5` stays a (rejected) property access and `1..5` scans as
+            // `1`, `.`, `.5`.
+            '.' if !self.after_operand && matches!(self.peek(), Some('0'..='9')) => {
+                return Some(self.scan_number(c));
+            }
+            '.' if self.peek() == Some('.') && self.peek_nth(1) == Some('.') => {
+                self.advance();
+                self.advance();
+                DotDotDot
+            }
+            '.' => Dot,
+            // Longest match wins, so `a-->b` is `a`, `--`, `>`, `b`.
+            '-' => {
+                if self.expect_next('-') {
+                    MinusMinus
+                } else if self.expect_next('=') {
+                    MinusEqual
+                } else if self.expect_next('>') {
+                    Arrow
+                } else {
+                    Minus
+                }
+            }
+            '+' => {
+                if self.expect_next('+') {
+                    PlusPlus
+                } else if self.expect_next('=') {
+                    PlusEqual
+                } else {
+                    Plus
+                }
+            }
+            ';' => Semicolon,
+            // Only adjacent stars make `**`; `a * *b` is two `*` tokens.
+            '*' => {
+                if self.expect_next('=') {
+                    StarEqual
+                } else if self.expect_next('*') {
+                    StarStar
+                } else {
+                    Star
+                }
+            }
+            '&' => Ampersand,
+            '|' => Pipe,
+            '^' => Caret,
+            '~' => Tilde,
+            '%' => {
+                if self.expect_next('=') {
+                    PercentEqual
+                } else {
+                    Percent
+                }
+            }
+            '!' => {
+                if self.expect_next('=') {
+                    BangEqual
+                } else {
+                    Bang
+                }
+            }
+            '=' => {
+                if self.expect_next('=') {
+                    EqualEqual
+                } else if self.expect_next('>') {
+                    FatArrow
+                } else {
+                    Equal
+                }
+            }
+            '<' => {
+                if self.expect_next('<') {
+                    LessLess
+                } else if self.expect_next('=') {
+                    LessEqual
+                } else {
+                    Less
+                }
+            }
+            '>' => {
+                if self.expect_next('>') {
+                    GreaterGreater
+                } else if self.expect_next('=') {
+                    GreaterEqual
+                } else {
+                    Greater
+                }
+            }
+            '/' => {
+                if self.expect_next('/') {
+                    self.skip_line();
+                    if !self.trivia {
+                        return None;
+                    }
+                    // `///` starts a doc comment; any further slashes, as in
+                    // `////`, are part of its text.
+                    let text = &self.source[self.start + 2..self.current];
+                    match text.strip_prefix('/') {
+                        Some(doc) => DocComment(doc.strip_prefix(' ').unwrap_or(doc).to_owned()),
+                        None => LineComment(text.to_owned()),
+                    }
+                } else if self.expect_next('*') {
+                    if let Err(error) = self.scan_multiline_comment() {
+                        return Some(Err(error));
+                    }
+                    if !self.trivia {
+                        return None;
                     }
+                    BlockComment(self.source[self.start + 2..self.current - 2].to_owned())
+                } else if self.expect_next('=') {
+                    SlashEqual
+                } else {
+                    Slash
                 }
-                '"' => self.scan_string(),
-                '0'..='9' => self.scan_number(),
-                c if is_alpha(c) => self.scan_identifier(),
-                ' ' | '\r' | '\t' => {}
-                '\n' => self.line += 1,
-                _ => panic!("Unexpected character at line {}", self.line),
             }
+            '#' if self.start == 0 && self.expect_next('!') => {
+                self.skip_line();
+                return None;
+            }
+            '"' => return Some(self.scan_string()),
+            'r' if self.expect_next('"') => return Some(self.scan_raw_string()),
+            '0'..='9' => return Some(self.scan_number(c)),
+            c if is_alpha(c) => self.scan_identifier(),
+            ' ' | '\t' => return None,
+            '\r' | '\n' => {
+                if c == '\r' {
+                    self.expect_next('\n');
+                }
+                self.newline();
+                return None;
+            }
+            c => return Some(Err(self.error(ScanErrorKind::UnexpectedCharacter(c)))),
+        };
+
+        Some(Ok(tok))
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.current += c.len_utf8();
+        self.column += 1;
+        Some(c)
+    }
+
+    fn peek(&self) -> Option<char> {
+        match self.source.as_bytes().get(self.current) {
+            Some(&byte) if byte.is_ascii() => Some(byte as char),
+            _ => self.peek_nth(0),
         }
+    }
 
-        self.tokens
+    /// Looks `n` characters past the next one; `peek_nth(0)` is `peek()`.
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.source[self.current..].chars().nth(n)
     }
 
-    fn next(&mut self) -> Option<char> {
-        self.current += 1;
-        self.stream.next()
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+        self.column = 1;
     }
 
-    fn peek(&mut self) -> Option<char> {
-        self.stream.peek().copied()
+    /// Bumps the line counter if the just-consumed `c` ends a line. `\n`,
+    /// `\r\n` and a lone `\r` all count once; for `\r\n` that is at the
+    /// `\n`.
+    fn track_newline(&mut self, c: char) {
+        if c == '\n' || (c == '\r' && self.peek() != Some('\n')) {
+            self.newline();
+        }
     }
 
-    fn is_eof(&mut self) -> bool {
-        self.peek().is_none()
+    /// Columns count characters, not bytes, from the start of the line.
+    fn column_at(&self, offset: usize) -> usize {
+        self.source[self.line_start..offset].chars().count() + 1
     }
 
-    fn add_token(&mut self, token: TokenKind) {
-        self.tokens.push(Token::new(token, self.line))
+    fn begin_token(&mut self) {
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_line_start = self.line_start;
+        self.start_column = self.column;
+    }
+
+    fn make_token(&mut self, kind: TokenKind) -> Token {
+        let lexeme = self.intern(&self.source[self.start..self.current]);
+        let mut token = Token::new(
+            kind,
+            lexeme,
+            self.start_line,
+            self.start_column,
+            self.start..self.current,
+        );
+        token.trivia = std::mem::take(&mut self.leading_trivia);
+        token
+    }
+
+    fn intern(&mut self, text: &'a str) -> Rc<str> {
+        self.interner
+            .entry(text)
+            .or_insert_with(|| text.into())
+            .clone()
+    }
+
+    fn add_trivia(&mut self) {
+        let text = &self.source[self.start..self.current];
+        let trivia = if text.starts_with("//") {
+            Trivia::LineComment(text.to_owned())
+        } else if text.starts_with("/*") {
+            Trivia::BlockComment(text.to_owned())
+        } else if text.starts_with("#!") {
+            Trivia::Shebang(text.to_owned())
+        } else if text.ends_with(['\n', '\r']) {
+            Trivia::Newline(text.to_owned())
+        } else {
+            if let Some(Trivia::Whitespace(run)) = self.leading_trivia.last_mut() {
+                run.push_str(text);
+                return;
+            }
+            Trivia::Whitespace(text.to_owned())
+        };
+        self.leading_trivia.push(trivia);
+    }
+
+    fn error(&self, kind: ScanErrorKind) -> ScanError {
+        ScanError::new(kind, self.start_line, self.start_column, self.start)
+    }
+
+    /// Reports an error at `offset` on the current line rather than at the
+    /// start of the token.
+    fn error_at(&self, kind: ScanErrorKind, offset: usize) -> ScanError {
+        ScanError::new(kind, self.line, self.column_at(offset), offset)
+    }
+
+    /// Reports an unterminated string and resumes scanning at the end of the
+    /// line it started on, so the rest of the file isn't swallowed.
+    fn unterminated_string(&mut self) -> ScanError {
+        let error = self.error(ScanErrorKind::UnterminatedString);
+
+        let rest = &self.source[self.start..];
+        self.current = self.start + rest.find(['\n', '\r']).unwrap_or(rest.len());
+        self.line = self.start_line;
+        self.line_start = self.start_line_start;
+        self.column = self.column_at(self.current);
+
+        error
+    }
+
+    fn check_string_len(&self, value: &str) -> Result<(), ScanError> {
+        let max_string_len = self.config.limits.max_string_len;
+        if value.len() > max_string_len {
+            let kind = ScanErrorKind::LimitExceeded("string length", max_string_len);
+            return Err(self.error(kind));
+        }
+        Ok(())
     }
 
     fn expect_next(&mut self, a: char) -> bool {
         if let Some(b) = self.peek() {
             if a == b {
-                self.next();
+                self.advance();
                 return true;
             }
         }
@@ -126,66 +481,300 @@ impl<'a> Scanner<'a> {
         false
     }
 
-    fn scan_string(&mut self) {
-        while let Some(c) = self.peek() {
+    /// Scans string contents up to the closing quote or the next `${`. The
+    /// latter yields a `StringPart` and leaves the scanner inside the
+    /// interpolated expression until the matching `}` resumes the string.
+    fn scan_string(&mut self) -> Result<TokenKind, ScanError> {
+        let mut value = String::new();
+        let mut error = None;
+
+        let interpolated = loop {
+            let Some(c) = self.advance() else {
+                return Err(self.unterminated_string());
+            };
+
             match c {
-                '"' => break,
-                '\n' => {
-                    self.line += 1;
-                    self.next();
+                '"' => break false,
+                '$' if self.expect_next('{') => {
+                    let offset = self.current - 2;
+                    let max_nesting = self.config.limits.max_nesting;
+                    if self.interpolations.len() >= max_nesting {
+                        let kind = ScanErrorKind::LimitExceeded("nesting depth", max_nesting);
+                        error.get_or_insert(self.error_at(kind, offset));
+                    }
+                    self.interpolations.push(Interpolation {
+                        depth: 0,
+                        line: self.line,
+                        column: self.column_at(offset),
+                        offset,
+                    });
+                    break true;
+                }
+                '\\' => {
+                    let escape = self.current - 1;
+                    match self.advance() {
+                        Some('n') => value.push('\n'),
+                        Some('t') => value.push('\t'),
+                        Some('r') => value.push('\r'),
+                        Some('\\') => value.push('\\'),
+                        Some('"') => value.push('"'),
+                        Some('0') => value.push('\0'),
+                        Some('$') => value.push('$'),
+                        Some('u') => match self.scan_unicode_escape() {
+                            Ok(c) => value.push(c),
+                            Err(reason) => {
+                                let escape_text = self.source[escape..self.current].to_owned();
+                                let kind = ScanErrorKind::InvalidUnicodeEscape(escape_text, reason);
+                                error.get_or_insert(self.error_at(kind, escape));
+                            }
+                        },
+                        Some(c) => {
+                            let kind = ScanErrorKind::InvalidEscape(c);
+                            error.get_or_insert(self.error_at(kind, escape));
+                            self.track_newline(c);
+                        }
+                        None => return Err(self.unterminated_string()),
+                    }
                 }
-                _ => {
-                    self.next();
+                c => {
+                    value.push(c);
+                    self.track_newline(c);
+                }
+            }
+        };
+
+        if let Err(limit) = self.check_string_len(&value) {
+            error.get_or_insert(limit);
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None if interpolated => Ok(TokenKind::StringPart(value)),
+            None => Ok(TokenKind::String(value)),
+        }
+    }
+
+    /// Scans `r"..."`, where backslashes are taken literally and only the
+    /// closing quote ends the literal.
+    fn scan_raw_string(&mut self) -> Result<TokenKind, ScanError> {
+        let mut value = String::new();
+
+        loop {
+            match self.advance() {
+                Some('"') => break,
+                Some(c) => {
+                    value.push(c);
+                    self.track_newline(c);
                 }
+                None => return Err(self.unterminated_string()),
             }
         }
+        self.check_string_len(&value)?;
+
+        Ok(TokenKind::String(value))
+    }
+
+    /// Scans the `{XXXX}` part of a `\u{XXXX}` escape, consuming only the
+    /// braces and hex digits so that a malformed escape never swallows the
+    /// closing quote.
+    fn scan_unicode_escape(&mut self) -> Result<char, &'static str> {
+        if !self.expect_next('{') {
+            return Err("expected '{' after '\\u'");
+        }
 
-        if self.is_eof() {
-            panic!("Unterminated string at line {}", self.line)
+        let mut code = 0u32;
+        let mut digits = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(16)) {
+            self.advance();
+            code = code.saturating_mul(16).saturating_add(digit);
+            digits += 1;
         }
 
-        let value = self.source[(self.start + 1)..self.current].to_owned();
-        self.add_token(TokenKind::String(value));
-        self.next();
+        if digits > 6 {
+            return Err("expected at most 6 hex digits");
+        }
+
+        if !self.expect_next('}') {
+            return Err(match self.peek() {
+                Some('"') | None => "missing closing '}'",
+                Some(_) => "expected hex digit",
+            });
+        }
+
+        if digits == 0 {
+            return Err("expected at least one hex digit");
+        }
+
+        char::from_u32(code).ok_or("not a valid Unicode scalar value")
     }
 
-    fn scan_number(&mut self) {
-        while let Some(c) = self.peek() {
-            match c {
-                '0'..='9' => {
-                    self.next();
+    fn scan_number(&mut self, first: char) -> Result<TokenKind, ScanError> {
+        if first == '0' {
+            if self.expect_next('x') || self.expect_next('X') {
+                return self.scan_radix_number(16, "hex");
+            } else if self.expect_next('o') || self.expect_next('O') {
+                return self.scan_radix_number(8, "octal");
+            } else if self.expect_next('b') || self.expect_next('B') {
+                return self.scan_radix_number(2, "binary");
+            }
+        }
+
+        let mut digits;
+        let mut underscore;
+        let mut float = first == '.';
+
+        if float {
+            digits = "0.".to_owned();
+            underscore = self.scan_digits(10, false, &mut digits);
+        } else {
+            digits = first.to_string();
+            underscore = self.scan_digits(10, true, &mut digits);
+
+            if let Some('.') = self.peek() {
+                if let Some('0'..='9') = self.peek_nth(1) {
+                    self.advance();
+                    digits.push('.');
+                    underscore = underscore.or(self.scan_digits(10, false, &mut digits));
+                    float = true;
                 }
-                _ => break,
             }
         }
 
-        if let Some('.') = self.peek() {
-            let mut temp_stream = self.stream.clone();
-            temp_stream.next();
-            if let Some('0'..='9') = temp_stream.peek() {
-                self.next();
+        if let Some('e' | 'E') = self.peek() {
+            let exponent = match self.peek_nth(1) {
+                Some('0'..='9') => true,
+                Some('+' | '-') => matches!(self.peek_nth(2), Some('0'..='9')),
+                _ => false,
+            };
 
-                while let Some(c) = self.peek() {
-                    match c {
-                        '0'..='9' => {
-                            self.next();
-                        }
-                        _ => break,
+            if exponent {
+                self.advance();
+                digits.push('e');
+                if let Some(sign @ ('+' | '-')) = self.peek() {
+                    self.advance();
+                    digits.push(sign);
+                }
+                underscore = underscore.or(self.scan_digits(10, false, &mut digits));
+                float = true;
+            }
+        }
+
+        if let Some(offset) = underscore {
+            return Err(self.misplaced_underscore(offset));
+        }
+
+        if float {
+            match digits.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(TokenKind::Float(value)),
+                Ok(_) => Err(self.malformed_number("number literal is too large")),
+                Err(error) => Err(self.malformed_number(&format!("internal error: {}", error))),
+            }
+        } else {
+            self.exact_integer(digits.parse().ok())
+        }
+    }
+
+    /// Scans the digits of a prefixed integer literal such as `0xFF`, `0o17`
+    /// or `0b1010`. The prefix has already been consumed.
+    fn scan_radix_number(&mut self, radix: u32, name: &str) -> Result<TokenKind, ScanError> {
+        let mut digits = String::new();
+        let underscore = self.scan_digits(radix, false, &mut digits);
+
+        let reason = match self.peek() {
+            Some(c) if is_alpha_numeric(c) => Some(format!("invalid {} digit '{}'", name, c)),
+            Some('.') if matches!(self.peek_nth(1), Some('0'..='9')) => {
+                self.advance();
+                Some(format!("{} literals cannot have a fractional part", name))
+            }
+            _ if digits.is_empty() => Some(format!("expected {} digits after prefix", name)),
+            _ => None,
+        };
+
+        if let Some(reason) = reason {
+            // Skip the rest of the malformed literal so it doesn't resurface
+            // as a bogus identifier or number.
+            while self.peek().is_some_and(is_alpha_numeric) {
+                self.advance();
+            }
+            let lexeme = self.source[self.start..self.current].to_owned();
+            return Err(self.error(ScanErrorKind::MalformedNumber(lexeme, reason)));
+        }
+
+        if let Some(offset) = underscore {
+            return Err(self.misplaced_underscore(offset));
+        }
+
+        digits
+            .chars()
+            .filter_map(|c| c.to_digit(radix))
+            .try_fold(0i64, |value, digit| {
+                value.checked_mul(radix as i64)?.checked_add(digit as i64)
+            })
+            .map_or_else(|| Err(self.integer_overflow()), |value| self.exact_integer(Some(value)))
+    }
+
+    /// An integer literal's token, or an error when it overflowed or when
+    /// Lox's numbers, which are `f64`s, can't hold it exactly. Beyond 2^53
+    /// that would silently round, so `9007199254740993` is rejected and
+    /// `9007199254740992` or `2 ** 60` in hex are not.
+    fn exact_integer(&self, value: Option<i64>) -> Result<TokenKind, ScanError> {
+        match value {
+            None => Err(self.integer_overflow()),
+            Some(value) if value as f64 as i128 != value as i128 => Err(
+                self.malformed_number("integer literal can't be represented exactly as a number"),
+            ),
+            Some(value) => Ok(TokenKind::Integer(value)),
+        }
+    }
+
+    /// Consumes a run of digits in `radix`, pushing them onto `digits` with
+    /// any `_` separators removed. A separator is only valid between two
+    /// digits; the offset of the first misplaced one is returned.
+    fn scan_digits(&mut self, radix: u32, after_digit: bool, digits: &mut String) -> Option<usize> {
+        let mut misplaced = None;
+        let mut after_digit = after_digit;
+
+        loop {
+            match self.peek() {
+                Some('_') => {
+                    let offset = self.current;
+                    self.advance();
+                    if !after_digit || !self.peek().is_some_and(|c| c.is_digit(radix)) {
+                        misplaced = misplaced.or(Some(offset));
                     }
+                    after_digit = false;
+                }
+                Some(c) if c.is_digit(radix) => {
+                    self.advance();
+                    digits.push(c);
+                    after_digit = true;
                 }
+                _ => break,
             }
         }
 
-        let value = self.source[self.start..self.current]
-            .parse::<f64>()
-            .unwrap();
-        self.add_token(TokenKind::Number(value));
+        misplaced
+    }
+
+    fn malformed_number(&self, reason: &str) -> ScanError {
+        let lexeme = self.source[self.start..self.current].to_owned();
+        self.error(ScanErrorKind::MalformedNumber(lexeme, reason.to_owned()))
+    }
+
+    fn integer_overflow(&self) -> ScanError {
+        self.malformed_number("integer literal does not fit in 64 bits")
+    }
+
+    fn misplaced_underscore(&self, offset: usize) -> ScanError {
+        let lexeme = self.source[self.start..self.current].to_owned();
+        let reason = "'_' must separate two digits".to_owned();
+        self.error_at(ScanErrorKind::MalformedNumber(lexeme, reason), offset)
     }
 
-    fn scan_identifier(&mut self) {
+    fn scan_identifier(&mut self) -> TokenKind {
         while let Some(c) = self.peek() {
             if is_alpha_numeric(c) {
-                self.next();
+                self.advance();
             } else {
                 break;
             }
@@ -193,70 +782,548 @@ impl<'a> Scanner<'a> {
 
         let value = &self.source[self.start..self.current];
 
-        use TokenKind::*;
-        let tok = match value {
-            "and" => And,
-            "class" => Class,
-            "else" => Else,
-            "false" => False,
-            "for" => For,
-            "fun" => Fun,
-            "if" => If,
-            "nil" => Nil,
-            "or" => Or,
-            "print" => Print,
-            "return" => Return,
-            "super" => Super,
-            "this" => This,
-            "true" => True,
-            "var" => Var,
-            "while" => While,
-            s => Identifier(s.to_owned()),
-        };
+        match self.config.keywords.get(value) {
+            Some(keyword) => keyword.clone(),
+            None => TokenKind::Identifier(self.intern(value)),
+        }
+    }
 
-        self.add_token(tok);
+    /// Skips to the end of the line, leaving the newline for the main loop.
+    fn skip_line(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '\n' || c == '\r' {
+                break;
+            }
+            self.advance();
+        }
     }
 
-    fn scan_multiline_comment(&mut self) {
+    fn scan_multiline_comment(&mut self) -> Result<(), ScanError> {
         let mut nesting = 0;
+        let mut error = None;
 
-        while let Some(c) = self.next() {
+        while let Some(c) = self.advance() {
             match c {
-                '/' => {
-                    if self.expect_next('*') {
-                        nesting += 1;
+                '/' if self.expect_next('*') => {
+                    nesting += 1;
+                    let max_nesting = self.config.limits.max_nesting;
+                    if nesting == max_nesting {
+                        let kind = ScanErrorKind::LimitExceeded("nesting depth", max_nesting);
+                        error = Some(self.error_at(kind, self.current - 2));
                     }
                 }
-
-                '*' => {
-                    if self.expect_next('/') {
-                        if nesting == 0 {
-                            break;
-                        } else {
-                            nesting -= 1
-                        }
+                '*' if self.expect_next('/') => {
+                    if nesting == 0 {
+                        return error.map_or(Ok(()), Err);
+                    } else {
+                        nesting -= 1
                     }
                 }
-                _ => {}
+                c => self.track_newline(c),
             }
         }
+
+        Err(self.error(ScanErrorKind::UnterminatedComment))
     }
 }
 
-fn is_digit(c: char) -> bool {
-    match c {
-        '0'..='9' => true,
-        _ => false,
+impl Iterator for Scanner<'_> {
+    type Item = Result<Token, ScanError>;
+
+    /// Yields the next token or lexical error, ending with a single `Eof`
+    /// token. Trivia is skipped, or attached to tokens in lossless mode.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            self.begin_token();
+
+            let Some(c) = self.advance() else {
+                if let Some(interpolation) = self.interpolations.pop() {
+                    return Some(Err(ScanError::new(
+                        ScanErrorKind::UnterminatedInterpolation,
+                        interpolation.line,
+                        interpolation.column,
+                        interpolation.offset,
+                    )));
+                }
+
+                self.finished = true;
+                return Some(Ok(self.make_token(TokenKind::Eof)));
+            };
+
+            match self.scan_token(c) {
+                Some(Ok(_)) if self.tokens == self.config.limits.max_tokens => {
+                    // Give up on the rest of the input; the next call yields
+                    // `Eof`.
+                    let max_tokens = self.config.limits.max_tokens;
+                    let error = self.error(ScanErrorKind::LimitExceeded("token count", max_tokens));
+                    self.current = self.source.len();
+                    self.interpolations.clear();
+                    return Some(Err(error));
+                }
+                Some(result) => {
+                    return Some(result.map(|kind| {
+                        self.tokens += 1;
+                        self.after_operand = ends_operand(&kind);
+                        self.make_token(kind)
+                    }))
+                }
+                None if self.lossless => self.add_trivia(),
+                None => {}
+            }
+        }
     }
 }
 
+fn ends_operand(kind: &TokenKind) -> bool {
+    use TokenKind::*;
+    matches!(
+        kind,
+        Identifier(_)
+            | String(_)
+            | Integer(_)
+            | Float(_)
+            | RightParen
+            | RightBracket
+            | This
+            | Super
+    )
+}
+
+/// Approximates Unicode `XID_Start` (plus `_`) using the standard library's
+/// alphabetic property.
 fn is_alpha(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '_' => true,
-        _ => false,
-    }
+    c == '_' || c.is_alphabetic()
 }
 
+/// Approximates Unicode `XID_Continue`: letters, digits, `_`, the common
+/// combining mark blocks and the zero-width joiners.
 fn is_alpha_numeric(c: char) -> bool {
-    is_alpha(c) || is_digit(c)
+    is_alpha(c)
+        || c.is_numeric()
+        || matches!(c,
+            '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{200C}'
+            | '\u{200D}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenKind::*;
+
+    /// The kinds `source` scans to, up to the final `Eof`.
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        let (tokens, errors) = Scanner::new(source).scan_tokens();
+        assert!(errors.is_empty(), "{:?}", errors);
+        let (eof, tokens) = tokens.split_last().expect("always ends with Eof");
+        assert!(*eof == Eof);
+        Token::kinds(tokens).into_iter().cloned().collect()
+    }
+
+    fn errors(source: &str) -> Vec<ScanErrorKind> {
+        let (_, errors) = Scanner::new(source).scan_tokens();
+        errors.into_iter().map(|error| error.kind).collect()
+    }
+
+    fn identifier(name: &str) -> TokenKind {
+        Identifier(name.into())
+    }
+
+    #[test]
+    fn single_character_operators() {
+        assert_eq!(
+            kinds("(){}[]:?,.-+;/ *%&|^~! = < >"),
+            [
+                LeftParen,
+                RightParen,
+                LeftBrace,
+                RightBrace,
+                LeftBracket,
+                RightBracket,
+                Colon,
+                Question,
+                Comma,
+                Dot,
+                Minus,
+                Plus,
+                Semicolon,
+                Slash,
+                Star,
+                Percent,
+                Ampersand,
+                Pipe,
+                Caret,
+                Tilde,
+                Bang,
+                Equal,
+                Less,
+                Greater,
+            ]
+        );
+    }
+
+    #[test]
+    fn two_and_three_character_operators() {
+        assert_eq!(
+            kinds("!= == >= <= << >> += -= *= /= %= ++ -- ** -> => ... ?? ?."),
+            [
+                BangEqual,
+                EqualEqual,
+                GreaterEqual,
+                LessEqual,
+                LessLess,
+                GreaterGreater,
+                PlusEqual,
+                MinusEqual,
+                StarEqual,
+                SlashEqual,
+                PercentEqual,
+                PlusPlus,
+                MinusMinus,
+                StarStar,
+                Arrow,
+                FatArrow,
+                DotDotDot,
+                QuestionQuestion,
+                QuestionDot,
+            ]
+        );
+    }
+
+    #[test]
+    fn compound_assignment_operators() {
+        for (source, operator) in [
+            ("x+=2", PlusEqual),
+            ("x-=2", MinusEqual),
+            ("x*=2", StarEqual),
+            ("x/=2", SlashEqual),
+            ("x%=2", PercentEqual),
+        ] {
+            assert_eq!(
+                kinds(source),
+                [identifier("x"), operator, Integer(2)],
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn longest_operator_wins() {
+        assert_eq!(
+            kinds("a-->b"),
+            [identifier("a"), MinusMinus, Greater, identifier("b")]
+        );
+        assert_eq!(
+            kinds("a * *b"),
+            [identifier("a"), Star, Star, identifier("b")]
+        );
+        assert_eq!(
+            kinds("a ?.5 : b"),
+            [
+                identifier("a"),
+                Question,
+                Float(0.5),
+                Colon,
+                identifier("b")
+            ]
+        );
+    }
+
+    #[test]
+    fn exponent_operator() {
+        // Two adjacent stars are always `**`; a space keeps them apart.
+        assert_eq!(kinds("2**3"), [Integer(2), StarStar, Integer(3)]);
+        assert_eq!(
+            kinds("a * *b"),
+            [identifier("a"), Star, Star, identifier("b")]
+        );
+        assert_eq!(
+            kinds("a***b"),
+            [identifier("a"), StarStar, Star, identifier("b")]
+        );
+    }
+
+    #[test]
+    fn keywords() {
+        for (name, kind) in KEYWORDS {
+            assert_eq!(kinds(name), std::slice::from_ref(kind), "{}", name);
+        }
+    }
+
+    #[test]
+    fn identifiers_that_start_like_keywords() {
+        assert_eq!(
+            kinds("classy orchid _if fun2 café"),
+            [
+                identifier("classy"),
+                identifier("orchid"),
+                identifier("_if"),
+                identifier("fun2"),
+                identifier("café"),
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_keywords() {
+        let mut config = ScannerConfig::default();
+        config.add_keyword("unless");
+        config.remove_keyword("print");
+        let (tokens, _) = Scanner::new_with_config("unless print", config).scan_tokens();
+        assert_eq!(
+            Token::kinds(&tokens),
+            [&Custom("unless".into()), &identifier("print"), &Eof]
+        );
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(
+            kinds("0 42 1_000 0xFF 0o17 0b101"),
+            [
+                Integer(0),
+                Integer(42),
+                Integer(1000),
+                Integer(255),
+                Integer(15),
+                Integer(5),
+            ]
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(
+            kinds(".5 3.25 1e3 2.5E-1"),
+            [Float(0.5), Float(3.25), Float(1000.0), Float(0.25)]
+        );
+        // A `.` straight after an operand is a property access.
+        assert_eq!(kinds("1..5"), [Integer(1), Dot, Float(0.5)]);
+        assert_eq!(kinds("1.foo"), [Integer(1), Dot, identifier("foo")]);
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        for source in [
+            "1__0",
+            "0x",
+            "0b102",
+            "0x1.5",
+            "99999999999999999999",
+            "1e999",
+            "1e309",
+            "9007199254740993",
+            "0x20000000000001",
+        ] {
+            assert!(
+                matches!(errors(source)[..], [ScanErrorKind::MalformedNumber(..)]),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn large_numbers_that_are_exact() {
+        assert_eq!(kinds("1e308"), [Float(1e308)]);
+        assert_eq!(
+            kinds("9007199254740992 0x1000000000000000"),
+            [Integer(1 << 53), Integer(1 << 60)]
+        );
+        assert_eq!(
+            errors("9007199254740993"),
+            [ScanErrorKind::MalformedNumber(
+                "9007199254740993".into(),
+                "integer literal can't be represented exactly as a number".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            kinds(r#""hi" "a\nb\t\"c\"" r"\n" "\u{41}\u{1F600}" """#),
+            [
+                String("hi".into()),
+                String("a\nb\t\"c\"".into()),
+                String("\\n".into()),
+                String("A\u{1F600}".into()),
+                String("".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn interpolated_strings() {
+        assert_eq!(
+            kinds(r#""a ${b} c ${ {"d": 1} } e""#),
+            [
+                StringPart("a ".into()),
+                identifier("b"),
+                StringPart(" c ".into()),
+                LeftBrace,
+                String("d".into()),
+                Colon,
+                Integer(1),
+                RightBrace,
+                String(" e".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_errors() {
+        assert_eq!(errors("\"open"), [ScanErrorKind::UnterminatedString]);
+        assert_eq!(errors(r#""\q""#), [ScanErrorKind::InvalidEscape('q')]);
+        assert!(matches!(
+            errors(r#""\u{110000}""#)[..],
+            [ScanErrorKind::InvalidUnicodeEscape(..)]
+        ));
+        // The quote starts a string inside the interpolation.
+        assert_eq!(
+            errors("\"${a\""),
+            [
+                ScanErrorKind::UnterminatedString,
+                ScanErrorKind::UnterminatedInterpolation,
+            ]
+        );
+        assert_eq!(errors("\"${a"), [ScanErrorKind::UnterminatedInterpolation]);
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(
+            kinds("a // line\n/* block /* nested */ */ b /// doc\n"),
+            [identifier("a"), identifier("b")]
+        );
+        assert_eq!(kinds("#!/usr/bin/env lox\nnil"), [Nil]);
+        assert_eq!(errors("/* open"), [ScanErrorKind::UnterminatedComment]);
+    }
+
+    #[test]
+    fn comments_as_trivia_tokens() {
+        let (tokens, _) = Scanner::new("a // line\n/* block */ /// doc")
+            .with_trivia(true)
+            .scan_tokens();
+        assert_eq!(
+            Token::kinds(&tokens),
+            [
+                &identifier("a"),
+                &LineComment(" line".into()),
+                &BlockComment(" block ".into()),
+                &DocComment("doc".into()),
+                &Eof,
+            ]
+        );
+    }
+
+    fn limited_errors(source: &str, limits: ScannerLimits) -> Vec<ScanErrorKind> {
+        let (_, errors) = Scanner::new_with_limits(source, limits).scan_tokens();
+        errors.into_iter().map(|error| error.kind).collect()
+    }
+
+    #[test]
+    fn string_length_limit() {
+        let limits = ScannerLimits {
+            max_string_len: 4,
+            ..ScannerLimits::default()
+        };
+        assert_eq!(limited_errors(r#""abcd" "${1}abcd""#, limits), []);
+        assert_eq!(
+            limited_errors(r#""abcde""#, limits),
+            [ScanErrorKind::LimitExceeded("string length", 4)]
+        );
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let limits = ScannerLimits {
+            max_nesting: 2,
+            ..ScannerLimits::default()
+        };
+        assert_eq!(limited_errors("/* /* */ */", limits), []);
+        assert_eq!(
+            limited_errors("/* /* /* */ */ */", limits),
+            [ScanErrorKind::LimitExceeded("nesting depth", 2)]
+        );
+        assert_eq!(limited_errors(r#""${ "${1}" }""#, limits), []);
+        assert_eq!(
+            limited_errors(r#""${ "${ "${1}" }" }""#, limits),
+            [ScanErrorKind::LimitExceeded("nesting depth", 2)]
+        );
+    }
+
+    #[test]
+    fn token_count_limit() {
+        let limits = ScannerLimits {
+            max_tokens: 3,
+            ..ScannerLimits::default()
+        };
+        assert_eq!(limited_errors("a + b", limits), []);
+        let (tokens, errors) = Scanner::new_with_limits("a + b;", limits).scan_tokens();
+        assert_eq!(
+            errors.into_iter().map(|error| error.kind).collect::<Vec<_>>(),
+            [ScanErrorKind::LimitExceeded("token count", 3)]
+        );
+        assert_eq!(tokens.last().map(|token| &token.kind), Some(&Eof));
+    }
+
+    #[test]
+    fn unexpected_characters() {
+        assert_eq!(
+            errors("a @ b #"),
+            [
+                ScanErrorKind::UnexpectedCharacter('@'),
+                ScanErrorKind::UnexpectedCharacter('#'),
+            ]
+        );
+    }
+
+    #[test]
+    fn positions() {
+        let (tokens, _) = Scanner::new("a\n  bc\r\n\"x\ny\" d").scan_tokens();
+        let positions: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.line, token.column, token.span.clone()))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (1, 1, 0..1),
+                (2, 3, 4..6),
+                (3, 1, 8..13),
+                (4, 4, 14..15),
+                (4, 5, 15..15)
+            ]
+        );
+    }
+
+    #[test]
+    fn lossless_trivia_reproduces_the_source() {
+        let source = "#!/bin/lox\n// intro\nvar  a =\t1; /* end */\r\n";
+        let (tokens, errors) = Scanner::new(source).with_lossless(true).scan_tokens();
+        assert!(errors.is_empty());
+        let mut text = std::string::String::new();
+        for token in &tokens {
+            for trivia in &token.trivia {
+                let (Trivia::Whitespace(s)
+                | Trivia::Newline(s)
+                | Trivia::LineComment(s)
+                | Trivia::BlockComment(s)
+                | Trivia::Shebang(s)) = trivia;
+                text.push_str(s);
+            }
+            text.push_str(&token.lexeme);
+        }
+        assert_eq!(text, source);
+    }
 }
@@ -0,0 +1,147 @@
+/// Maps byte offsets in a source text to 1-based lines and columns and
+/// back. Line endings are `\n`, `\r\n` or a lone `\r`, as in the scanner,
+/// and columns count characters rather than bytes.
+#[derive(Debug, Clone)]
+pub struct SourceMap<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let bytes = source.as_bytes();
+        let mut line_starts = vec![0];
+        for (i, &byte) in bytes.iter().enumerate() {
+            if byte == b'\n' || (byte == b'\r' && bytes.get(i + 1) != Some(&b'\n')) {
+                line_starts.push(i + 1);
+            }
+        }
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The line and column of `offset`. Offsets past the end map to the end
+    /// of the source, and one inside a multi-byte character to that
+    /// character.
+    pub fn location(&self, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        (line, self.source[line_start..offset].chars().count() + 1)
+    }
+
+    /// The byte offset of `line` and `column`, if they are inside the
+    /// source. The column just past the end of a line is allowed.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let text = self.line_text(line)?;
+        let line_start = self.line_starts[line - 1];
+        text.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .nth(column.checked_sub(1)?)
+            .map(|i| line_start + i)
+    }
+
+    /// The text of `line`, without its line ending.
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.source.len());
+        let text = &self.source[start..end];
+        Some(
+            text.strip_suffix("\r\n")
+                .or_else(|| text.strip_suffix(['\n', '\r']))
+                .unwrap_or(text),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_starts() {
+        let map = SourceMap::new("ab\ncd\n");
+        assert_eq!(map.line_count(), 3);
+        assert_eq!(map.location(0), (1, 1));
+        assert_eq!(map.location(2), (1, 3));
+        assert_eq!(map.location(3), (2, 1));
+        assert_eq!(map.location(4), (2, 2));
+        assert_eq!(map.location(6), (3, 1));
+    }
+
+    #[test]
+    fn line_endings() {
+        let map = SourceMap::new("a\r\nb\rc\nd");
+        assert_eq!(map.line_count(), 4);
+        // The `\n` of a `\r\n` is still on the line it ends.
+        assert_eq!(map.location(2), (1, 3));
+        assert_eq!(map.location(3), (2, 1));
+        assert_eq!(map.location(5), (3, 1));
+        assert_eq!(map.location(7), (4, 1));
+        let lines: Vec<_> = (1..=4).filter_map(|line| map.line_text(line)).collect();
+        assert_eq!(lines, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn end_of_input() {
+        let map = SourceMap::new("ab\ncd");
+        assert_eq!(map.line_count(), 2);
+        assert_eq!(map.location(5), (2, 3));
+        assert_eq!(map.location(100), (2, 3));
+        assert_eq!(map.line_text(2), Some("cd"));
+        assert_eq!(map.line_text(3), None);
+        assert_eq!(map.offset(2, 3), Some(5));
+        assert_eq!(map.offset(2, 4), None);
+    }
+
+    #[test]
+    fn empty_source() {
+        let map = SourceMap::new("");
+        assert_eq!(map.line_count(), 1);
+        assert_eq!(map.location(0), (1, 1));
+        assert_eq!(map.line_text(1), Some(""));
+        assert_eq!(map.line_text(0), None);
+        assert_eq!(map.offset(1, 1), Some(0));
+        assert_eq!(map.offset(1, 0), None);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let map = SourceMap::new("é = \"日本\";");
+        assert_eq!(map.location(2), (1, 2));
+        assert_eq!(map.location(6), (1, 6));
+        assert_eq!(map.location(9), (1, 7));
+        // An offset inside a character maps to that character.
+        assert_eq!(map.location(1), (1, 1));
+        assert_eq!(map.location(7), (1, 6));
+        assert_eq!(map.offset(1, 7), Some(9));
+    }
+
+    #[test]
+    fn offsets_round_trip() {
+        let source = "fun f() {\r\n  return \"ü\";\n}";
+        let map = SourceMap::new(source);
+        let characters = source
+            .char_indices()
+            .filter(|(_, c)| !matches!(c, '\r' | '\n'));
+        for (offset, _) in characters {
+            let (line, column) = map.location(offset);
+            assert_eq!(map.offset(line, column), Some(offset), "{}", offset);
+        }
+    }
+}
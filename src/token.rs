@@ -1,79 +1,195 @@
+use std::ops::Range;
+use std::rc::Rc;
+
 #[rustfmt::skip]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenKind {
     // Single-character tokens
     LeftParen, RightParen, LeftBrace, RightBrace,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    LeftBracket, RightBracket, Colon, Question,
+    Comma, Dot, Minus, Plus, Semicolon, Slash, Star, Percent,
+    Ampersand, Pipe, Caret, Tilde,
 
     // One or two character tokens.
     Bang, BangEqual,
     Equal, EqualEqual,
     Greater, GreaterEqual,
     Less, LessEqual,
+    LessLess, GreaterGreater,
+    PlusEqual, MinusEqual, StarEqual, SlashEqual, PercentEqual,
+    PlusPlus, MinusMinus, StarStar,
+    Arrow, FatArrow, DotDotDot,
+    QuestionQuestion, QuestionDot,
 
     // Literals.
-    Identifier(String), String(String), Number(f64),
+    Identifier(Rc<str>), String(String), Integer(i64), Float(f64),
+    // A string segment that ends in `${`; the interpolated expression's
+    // tokens follow, then another `StringPart` or a closing `String`.
+    StringPart(String),
 
     // Keywords.
-    And, Class, Else, False, Fun, For, If, Nil, Or,
-    Print, Return, Super, This, True, Var, While,
+    And, As, Break, Case, Catch, Class, Const, Continue, Default, Else, False,
+    Finally, Fun, For, If, Import, In, Nil, Or, Print, Return, Static, Super, Switch,
+    This, Throw, True, Try, Var, While,
+    // A keyword added through `ScannerConfig`.
+    Custom(String),
+
+    // Trivia, only emitted when the scanner is asked for it.
+    LineComment(String), BlockComment(String),
+    // A `///` comment, minus the slashes and one leading space.
+    DocComment(String),
+
+    Eof,
+}
+
+/// Source text skipped between tokens, kept verbatim in lossless mode.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trivia {
+    Whitespace(String),
+    Newline(String),
+    LineComment(String),
+    BlockComment(String),
+    Shebang(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
     pub kind: TokenKind,
+    pub lexeme: Rc<str>,
     pub line: usize,
+    pub column: usize,
+    pub span: Range<usize>,
+    pub trivia: Vec<Trivia>,
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, line: usize) -> Self {
-        Self { kind, line }
+    pub fn new(
+        kind: TokenKind,
+        lexeme: Rc<str>,
+        line: usize,
+        column: usize,
+        span: Range<usize>,
+    ) -> Self {
+        Self {
+            kind,
+            lexeme,
+            line,
+            column,
+            span,
+            trivia: vec![],
+        }
+    }
+
+    /// The kinds of `tokens`, for comparisons that don't care about
+    /// positions.
+    pub fn kinds(tokens: &[Token]) -> Vec<&TokenKind> {
+        tokens.iter().map(|token| &token.kind).collect()
     }
 }
 
-impl std::fmt::Display for Token {
+impl PartialEq<TokenKind> for Token {
+    fn eq(&self, kind: &TokenKind) -> bool {
+        self.kind == *kind
+    }
+}
+
+impl std::fmt::Display for TokenKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use TokenKind::*;
-        write!(f, "{}", match &self.kind {
-            LeftParen => "(".to_string(),
-            RightParen => ")".to_string(),
-            LeftBrace => "{".to_string(),
-            RightBrace => "}".to_string(),
-            Comma => ",".to_string(),
-            Dot => ".".to_string(),
-            Minus => "-".to_string(),
-            Plus => "+".to_string(),
-            Semicolon => ";".to_string(),
-            Slash => "/".to_string(),
-            Star => "*".to_string(),
-            Bang => "!".to_string(),
-            BangEqual => "!=".to_string(),
-            Equal => "=".to_string(),
-            EqualEqual => "==".to_string(),
-            Greater => ">".to_string(),
-            GreaterEqual => ">=".to_string(),
-            Less => "<".to_string(),
-            LessEqual => "<=".to_string(),
-            Identifier(id) => id.to_string(),
-            String(s) => format!("\"{}\"", s),
-            Number(n) => format!("{}", n),
-            And => "and".to_string(),
-            Class => "class".to_string(),
-            Else => "else".to_string(),
-            False => "false".to_string(),
-            Fun => "fun".to_string(),
-            For => "for".to_string(),
-            If => "if".to_string(),
-            Nil => "nil".to_string(),
-            Or => "or".to_string(),
-            Print => "print".to_string(),
-            Return => "return".to_string(),
-            Super => "super".to_string(),
-            This => "this".to_string(),
-            True => "true".to_string(),
-            Var => "var".to_string(),
-            While => "while".to_string(),
-        }).unwrap();
-        Ok(())
+        let text = match self {
+            LeftParen => "(",
+            RightParen => ")",
+            LeftBrace => "{",
+            RightBrace => "}",
+            LeftBracket => "[",
+            RightBracket => "]",
+            Colon => ":",
+            Question => "?",
+            Comma => ",",
+            Dot => ".",
+            Minus => "-",
+            Plus => "+",
+            Semicolon => ";",
+            Slash => "/",
+            Star => "*",
+            Percent => "%",
+            Ampersand => "&",
+            Pipe => "|",
+            Caret => "^",
+            Tilde => "~",
+            Bang => "!",
+            BangEqual => "!=",
+            Equal => "=",
+            EqualEqual => "==",
+            Greater => ">",
+            GreaterEqual => ">=",
+            Less => "<",
+            LessEqual => "<=",
+            LessLess => "<<",
+            GreaterGreater => ">>",
+            PlusEqual => "+=",
+            MinusEqual => "-=",
+            StarEqual => "*=",
+            SlashEqual => "/=",
+            PercentEqual => "%=",
+            PlusPlus => "++",
+            MinusMinus => "--",
+            StarStar => "**",
+            Arrow => "->",
+            FatArrow => "=>",
+            DotDotDot => "...",
+            QuestionQuestion => "??",
+            QuestionDot => "?.",
+            And => "and",
+            As => "as",
+            Break => "break",
+            Case => "case",
+            Catch => "catch",
+            Class => "class",
+            Const => "const",
+            Continue => "continue",
+            Default => "default",
+            Else => "else",
+            False => "false",
+            Finally => "finally",
+            Fun => "fun",
+            For => "for",
+            If => "if",
+            Import => "import",
+            In => "in",
+            Nil => "nil",
+            Or => "or",
+            Print => "print",
+            Return => "return",
+            Static => "static",
+            Super => "super",
+            Switch => "switch",
+            This => "this",
+            Throw => "throw",
+            True => "true",
+            Try => "try",
+            Var => "var",
+            While => "while",
+            Identifier(id) => id,
+            Custom(keyword) => keyword,
+            String(s) => return write!(f, "\"{}\"", s.escape_debug()),
+            StringPart(s) => return write!(f, "\"{}${{", s.escape_debug()),
+            Integer(n) => return write!(f, "{}", n),
+            Float(n) => return write!(f, "{:?}", n),
+            LineComment(s) => return write!(f, "//{}", s.escape_debug()),
+            DocComment(s) => return write!(f, "/// {}", s.escape_debug()),
+            BlockComment(s) => return write!(f, "/*{}*/", s.escape_debug()),
+            Eof => "EOF",
+        };
+        f.write_str(text)
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{} {}", self.line, self.column, self.kind)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,458 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::{Arena, Function, Stmt};
+use crate::environment::Environment;
+use crate::resolver::Locals;
+use crate::token::TokenKind;
+
+/// A Lox runtime value. Strings are immutable and shared; lists, maps and
+/// instances are shared and mutable, so copying one aliases it.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(Rc<str>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<Map>>),
+    Callable(Callable),
+    Instance(Rc<RefCell<Instance>>),
+}
+
+impl Value {
+    pub fn list(elements: Vec<Value>) -> Self {
+        Value::List(Rc::new(RefCell::new(elements)))
+    }
+
+    /// `nil` and `false` are falsey; everything else is truthy.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    /// The name of the value's type, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Callable(Callable::Class(_)) => "class",
+            Value::Callable(_) => "function",
+            Value::Instance(_) => "instance",
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.into())
+    }
+}
+
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::from(c.encode_utf8(&mut [0; 4]) as &str)
+    }
+}
+
+/// Lox `==`: values of different types are never equal, numbers compare
+/// as IEEE doubles, strings by content, lists element by element and maps
+/// entry by entry in any order. Functions, classes and instances compare
+/// by identity.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        equal(self, other, &mut vec![])
+    }
+}
+
+/// Compares `a` and `b`, where `open` holds the pairs of lists or maps
+/// already being compared. Meeting one of those again means a cycle, which
+/// is taken as equal so far; the rest of the comparison decides.
+fn equal(a: &Value, b: &Value, open: &mut Vec<(*const (), *const ())>) -> bool {
+    match (a, b) {
+        (Value::Nil, Value::Nil) => true,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Number(a), Value::Number(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a == b,
+        (Value::List(a), Value::List(b)) => {
+            let ids = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+            if open.contains(&ids) {
+                return true;
+            }
+            let (a, b) = (a.borrow(), b.borrow());
+            if a.len() != b.len() {
+                return false;
+            }
+            open.push(ids);
+            let result = a.iter().zip(b.iter()).all(|(a, b)| equal(a, b, open));
+            open.pop();
+            result
+        }
+        (Value::Map(a), Value::Map(b)) => {
+            let ids = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+            if open.contains(&ids) {
+                return true;
+            }
+            let (a, b) = (a.borrow(), b.borrow());
+            if a.len() != b.len() {
+                return false;
+            }
+            open.push(ids);
+            let result = a
+                .iter()
+                .all(|(key, a)| b.get(key).is_some_and(|b| equal(a, b, open)));
+            open.pop();
+            result
+        }
+        (Value::Callable(a), Value::Callable(b)) => a == b,
+        (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+/// How `print` and string interpolation show a value. Strings inside lists
+/// and maps are quoted, and a list or map that contains itself shows the
+/// repeat as `[...]` or `{...}`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, false, &mut vec![])
+    }
+}
+
+fn write_value(
+    f: &mut fmt::Formatter<'_>,
+    value: &Value,
+    quoted: bool,
+    open: &mut Vec<*const ()>,
+) -> fmt::Result {
+    match value {
+        Value::Nil => f.write_str("nil"),
+        Value::Bool(b) => write!(f, "{}", b),
+        Value::Number(n) => write_number(f, *n),
+        Value::String(s) if quoted => write!(f, "\"{}\"", s.escape_debug()),
+        Value::String(s) => f.write_str(s),
+        Value::List(list) => {
+            let id = Rc::as_ptr(list) as *const ();
+            if open.contains(&id) {
+                return f.write_str("[...]");
+            }
+            open.push(id);
+            f.write_str("[")?;
+            for (i, element) in list.borrow().iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_value(f, element, true, open)?;
+            }
+            open.pop();
+            f.write_str("]")
+        }
+        Value::Map(map) => {
+            let id = Rc::as_ptr(map) as *const ();
+            if open.contains(&id) {
+                return f.write_str("{...}");
+            }
+            open.push(id);
+            f.write_str("{")?;
+            for (i, (key, value)) in map.borrow().iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_value(f, &key.to_value(), true, open)?;
+                f.write_str(": ")?;
+                write_value(f, value, true, open)?;
+            }
+            open.pop();
+            f.write_str("}")
+        }
+        Value::Callable(callable) => write!(f, "{}", callable),
+        Value::Instance(instance) => write!(f, "{} instance", instance.borrow().class.name),
+    }
+}
+
+/// Numbers print in their shortest form that reads back the same, so
+/// integers have no decimal point. `-0` keeps its sign, and NaN and the
+/// infinities print as `nan`, `inf` and `-inf`.
+fn write_number(f: &mut fmt::Formatter<'_>, n: f64) -> fmt::Result {
+    match n {
+        n if n.is_nan() => f.write_str("nan"),
+        f64::INFINITY => f.write_str("inf"),
+        f64::NEG_INFINITY => f.write_str("-inf"),
+        n => write!(f, "{}", n),
+    }
+}
+
+/// Something that can be called with arguments.
+#[derive(Debug, Clone)]
+pub enum Callable {
+    Native(Rc<NativeFunction>),
+    /// A built-in method of a list, map or string, bound to it.
+    NativeMethod(Rc<NativeMethod>),
+    Function(Rc<LoxFunction>),
+    /// Calling a class creates an instance of it.
+    Class(Rc<Class>),
+}
+
+impl Callable {
+    pub fn name(&self) -> &str {
+        match self {
+            Callable::Native(native) => native.name,
+            Callable::NativeMethod(method) => method.native.name,
+            Callable::Function(function) => function.name(),
+            Callable::Class(class) => &class.name,
+        }
+    }
+
+    /// The fewest and the most arguments a call can pass, where `None`
+    /// means there is no limit.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Callable::Native(native) => (native.arity, Some(native.arity)),
+            Callable::NativeMethod(method) => (method.native.arity, Some(method.native.arity)),
+            Callable::Function(function) => function.arity(),
+            Callable::Class(class) => class
+                .find_method("init")
+                .map_or((0, Some(0)), |init| init.arity()),
+        }
+    }
+}
+
+/// Callables are equal when they are the same object.
+impl PartialEq for Callable {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Callable::Native(a), Callable::Native(b)) => Rc::ptr_eq(a, b),
+            (Callable::NativeMethod(a), Callable::NativeMethod(b)) => Rc::ptr_eq(a, b),
+            (Callable::Function(a), Callable::Function(b)) => Rc::ptr_eq(a, b),
+            (Callable::Class(a), Callable::Class(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Callable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Callable::Native(native) => write!(f, "<native fn {}>", native.name),
+            Callable::NativeMethod(method) => write!(f, "<native fn {}>", method.native.name),
+            Callable::Function(function) => write!(f, "<fn {}>", function.name()),
+            Callable::Class(class) => f.write_str(&class.name),
+        }
+    }
+}
+
+/// A function declared in Lox, or a lambda, with the scope it was
+/// declared in. Calls run the body in a new scope inside `closure`, so
+/// the function sees that scope's variables as they are at the call.
+pub struct LoxFunction {
+    pub declaration: Rc<Function>,
+    pub closure: Rc<RefCell<Environment>>,
+    /// How the program declaring the function resolved its body.
+    pub locals: Rc<Locals>,
+    /// The expressions of the program declaring the function.
+    pub exprs: Rc<Arena>,
+    /// Whether this is a class's `init` method, which returns `this`.
+    pub is_initializer: bool,
+}
+
+impl LoxFunction {
+    pub fn name(&self) -> &str {
+        match self.declaration.name.kind {
+            TokenKind::Fun => "lambda",
+            _ => &self.declaration.name.lexeme,
+        }
+    }
+
+    /// Parameters with defaults can be left out, and a rest parameter
+    /// takes any number of extra arguments.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        let params = &self.declaration.params;
+        let required = params
+            .iter()
+            .filter(|param| param.default.is_none())
+            .count();
+        let most = self.declaration.rest.is_none().then_some(params.len());
+        (required, most)
+    }
+
+    /// The method with `this` bound to `instance`, in a scope between the
+    /// method's closure and its calls.
+    pub fn bind(&self, instance: Rc<RefCell<Instance>>) -> LoxFunction {
+        let mut scope = Environment::with_enclosing(self.closure.clone());
+        scope.define("this", Value::Instance(instance));
+        LoxFunction {
+            declaration: self.declaration.clone(),
+            closure: Rc::new(RefCell::new(scope)),
+            locals: self.locals.clone(),
+            exprs: self.exprs.clone(),
+            is_initializer: self.is_initializer,
+        }
+    }
+}
+
+/// Only the name: the closure can contain the function itself.
+impl fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoxFunction")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A function implemented in Rust. It returns a message on failure, which
+/// the interpreter reports as a runtime error at the call.
+#[derive(Debug)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: fn(&[Value]) -> Result<Value, String>,
+}
+
+/// A built-in method with the value it was read from, which calls pass
+/// ahead of their arguments.
+#[derive(Debug)]
+pub struct NativeMethod {
+    pub receiver: Value,
+    pub native: &'static NativeFunction,
+}
+
+/// A class and its methods, which close over the scope the class was
+/// declared in.
+pub struct Class {
+    pub name: Rc<str>,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: HashMap<Rc<str>, Rc<LoxFunction>>,
+    pub static_methods: HashMap<Rc<str>, Rc<LoxFunction>>,
+    /// The `var` declarations in the class body, run for each new
+    /// instance with `this` bound to it.
+    pub fields: Vec<Stmt>,
+    pub closure: Rc<RefCell<Environment>>,
+    pub locals: Rc<Locals>,
+    pub exprs: Rc<Arena>,
+}
+
+impl Class {
+    /// Looks `name` up in this class, then up its superclass chain.
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        match (self.methods.get(name), &self.superclass) {
+            (Some(method), _) => Some(method.clone()),
+            (None, Some(superclass)) => superclass.find_method(name),
+            (None, None) => None,
+        }
+    }
+
+    /// Like `find_method`, for static methods.
+    pub fn find_static_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        match (self.static_methods.get(name), &self.superclass) {
+            (Some(method), _) => Some(method.clone()),
+            (None, Some(superclass)) => superclass.find_static_method(name),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Only the name, as for `LoxFunction`.
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Class")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An object created from a class, with its own fields.
+#[derive(Debug)]
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: HashMap<Rc<str>, Value>,
+}
+
+/// A map key. Keys match when they are `==`: numbers compare by value,
+/// with `-0` and `0` the same key, and strings by content. Only `nil`,
+/// booleans, numbers other than NaN and strings can be keys, as the other
+/// values are mutable or compare by identity.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Nil,
+    Bool(bool),
+    Number(u64),
+    String(Rc<str>),
+}
+
+impl Key {
+    pub fn from_value(value: &Value) -> Option<Key> {
+        match value {
+            Value::Nil => Some(Key::Nil),
+            Value::Bool(b) => Some(Key::Bool(*b)),
+            Value::Number(n) if n.is_nan() => None,
+            Value::Number(n) if *n == 0.0 => Some(Key::Number(0.0f64.to_bits())),
+            Value::Number(n) => Some(Key::Number(n.to_bits())),
+            Value::String(s) => Some(Key::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Key::Nil => Value::Nil,
+            Key::Bool(b) => Value::Bool(*b),
+            Key::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            Key::String(s) => Value::String(s.clone()),
+        }
+    }
+}
+
+/// The entries of a Lox map, kept in insertion order.
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    entries: Vec<(Key, Value)>,
+    index: HashMap<Key, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&Value> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    /// Sets `key`, keeping its original position if it was already there.
+    pub fn insert(&mut self, key: Key, value: Value) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    /// Removes `key`, keeping the order of the other entries.
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for (key, _) in &self.entries[i..] {
+            *self.index.get_mut(key).expect("every entry is indexed") -= 1;
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
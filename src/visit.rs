@@ -0,0 +1,230 @@
+use crate::ast::{Arena, Expr, ExprId, ExprKind, Function, Stmt, StmtKind};
+
+/// A read-only pass over the AST. Each method defaults to visiting the
+/// node's children through the matching `walk_*` function, so a pass only
+/// overrides the nodes it cares about and calls `walk_*` itself to keep
+/// descending. Child expressions are looked up in the arena `exprs`
+/// returns:
+///
+/// ```
+/// use lox_rs::ast::{Arena, Expr, ExprKind};
+/// use lox_rs::visit::{walk_expr, Visitor};
+///
+/// struct CountCalls<'a> {
+///     exprs: &'a Arena,
+///     calls: usize,
+/// }
+///
+/// impl<'a> Visitor<'a> for CountCalls<'a> {
+///     fn exprs(&self) -> &'a Arena {
+///         self.exprs
+///     }
+///
+///     fn visit_expr(&mut self, expr: &Expr) {
+///         if let ExprKind::Call { .. } = expr.kind {
+///             self.calls += 1;
+///         }
+///         walk_expr(self, expr);
+///     }
+/// }
+/// ```
+pub trait Visitor<'a>: Sized {
+    /// The arena the visited expressions live in.
+    fn exprs(&self) -> &'a Arena;
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    /// Function declarations, methods and lambdas.
+    fn visit_function(&mut self, function: &Function) {
+        walk_function(self, function);
+    }
+}
+
+pub fn walk_expr<'a, V: Visitor<'a>>(visitor: &mut V, expr: &Expr) {
+    match &expr.kind {
+        ExprKind::Literal(_)
+        | ExprKind::Variable { .. }
+        | ExprKind::This { .. }
+        | ExprKind::Super { .. } => {}
+        ExprKind::Grouping(expr) => visit(visitor, *expr),
+        ExprKind::Assign { value, .. } => visit(visitor, *value),
+        ExprKind::Unary { right, .. } => visit(visitor, *right),
+        ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+            visit(visitor, *left);
+            visit(visitor, *right);
+        }
+        ExprKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            visit(visitor, *condition);
+            visit(visitor, *then_branch);
+            visit(visitor, *else_branch);
+        }
+        ExprKind::Call {
+            callee, arguments, ..
+        } => {
+            visit(visitor, *callee);
+            arguments
+                .iter()
+                .for_each(|argument| visit(visitor, *argument));
+        }
+        ExprKind::Get { object, .. } => visit(visitor, *object),
+        ExprKind::Set { object, value, .. } => {
+            visit(visitor, *object);
+            visit(visitor, *value);
+        }
+        ExprKind::AssignPattern { value, .. } => visit(visitor, *value),
+        ExprKind::Postfix { target, .. } => visit(visitor, *target),
+        ExprKind::OptionalChain(expr) => visit(visitor, *expr),
+        ExprKind::List { elements } => {
+            elements.iter().for_each(|element| visit(visitor, *element));
+        }
+        ExprKind::Map { entries, .. } => {
+            for (key, value) in entries {
+                visit(visitor, *key);
+                visit(visitor, *value);
+            }
+        }
+        ExprKind::Index { object, index, .. } => {
+            visit(visitor, *object);
+            visit(visitor, *index);
+        }
+        ExprKind::IndexSet {
+            object,
+            index,
+            value,
+            ..
+        } => {
+            visit(visitor, *object);
+            visit(visitor, *index);
+            visit(visitor, *value);
+        }
+        ExprKind::Lambda(function) => visitor.visit_function(function),
+        ExprKind::Interpolation { parts } => {
+            parts.iter().for_each(|part| visit(visitor, *part));
+        }
+    }
+}
+
+pub fn walk_stmt<'a, V: Visitor<'a>>(visitor: &mut V, stmt: &Stmt) {
+    match &stmt.kind {
+        StmtKind::Expression(expr) | StmtKind::Print(expr) => visit(visitor, *expr),
+        StmtKind::Var { initializer, .. } => {
+            if let Some(initializer) = initializer {
+                visit(visitor, *initializer);
+            }
+        }
+        StmtKind::VarPattern { initializer, .. } => visit(visitor, *initializer),
+        StmtKind::Block(statements) => walk_stmts(visitor, statements),
+        StmtKind::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            visit(visitor, *condition);
+            visitor.visit_stmt(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_stmt(else_branch);
+            }
+        }
+        StmtKind::While {
+            condition,
+            body,
+            increment,
+            ..
+        } => {
+            visit(visitor, *condition);
+            visitor.visit_stmt(body);
+            if let Some(increment) = increment {
+                visit(visitor, *increment);
+            }
+        }
+        StmtKind::ForIn { iterable, body, .. } => {
+            visit(visitor, *iterable);
+            visitor.visit_stmt(body);
+        }
+        StmtKind::Break { .. } | StmtKind::Continue { .. } | StmtKind::Import { .. } => {}
+        StmtKind::Switch {
+            subject,
+            cases,
+            default,
+            ..
+        } => {
+            visit(visitor, *subject);
+            for case in cases {
+                visit(visitor, case.value);
+                walk_stmts(visitor, &case.body);
+            }
+            if let Some(default) = default {
+                walk_stmts(visitor, default);
+            }
+        }
+        StmtKind::Function(function) => visitor.visit_function(function),
+        StmtKind::Return { value, .. } => {
+            if let Some(value) = value {
+                visit(visitor, *value);
+            }
+        }
+        StmtKind::Throw { value, .. } => visit(visitor, *value),
+        StmtKind::Try {
+            body,
+            catch,
+            finally,
+            ..
+        } => {
+            walk_stmts(visitor, body);
+            if let Some(catch) = catch {
+                walk_stmts(visitor, &catch.body);
+            }
+            if let Some(finally) = finally {
+                walk_stmts(visitor, finally);
+            }
+        }
+        StmtKind::Class {
+            superclass,
+            fields,
+            methods,
+            static_methods,
+            ..
+        } => {
+            if let Some(superclass) = superclass {
+                visit(visitor, *superclass);
+            }
+            walk_stmts(visitor, fields);
+            for method in methods.iter().chain(static_methods) {
+                visitor.visit_function(method);
+            }
+        }
+    }
+}
+
+/// Visits the parameter defaults, then the body.
+pub fn walk_function<'a, V: Visitor<'a>>(visitor: &mut V, function: &Function) {
+    for param in &function.params {
+        if let Some(default) = &param.default {
+            visit(visitor, *default);
+        }
+    }
+    walk_stmts(visitor, &function.body);
+}
+
+pub fn walk_stmts<'a, V: Visitor<'a>>(visitor: &mut V, statements: &[Stmt]) {
+    statements
+        .iter()
+        .for_each(|statement| visitor.visit_stmt(statement));
+}
+
+fn visit<'a, V: Visitor<'a>>(visitor: &mut V, expr: ExprId) {
+    let exprs = visitor.exprs();
+    visitor.visit_expr(&exprs[expr]);
+}
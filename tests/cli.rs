@@ -0,0 +1,230 @@
+//! The driver's output modes, checked against golden files in
+//! `tests/golden`: a program `name.lox` and what a mode prints for it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn golden(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+}
+
+fn lox(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox-rs"))
+        .args(args)
+        .output()
+        .expect("Cannot run the driver")
+}
+
+#[test]
+fn tokens_dump_matches_golden_output() {
+    let program = golden("tokens.lox");
+    let output = lox(&["--tokens", program.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    let expected = fs::read_to_string(golden("tokens.txt")).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+#[test]
+fn dot_dump_matches_golden_output() {
+    let program = golden("dot.lox");
+    let output = lox(&["--ast-dot", program.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    let expected = fs::read_to_string(golden("dot.txt")).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+/// Prints each program in `tests/golden/ast` with `--ast` and compares it
+/// with the `.txt` file of the same name.
+#[test]
+fn ast_dumps_match_golden_output() {
+    let mut programs: Vec<_> = fs::read_dir(golden("ast"))
+        .expect("Cannot read tests/golden/ast")
+        .map(|entry| entry.expect("Cannot read tests/golden/ast").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+        .collect();
+    programs.sort();
+    assert!(!programs.is_empty(), "no programs in tests/golden/ast");
+
+    let failures: Vec<String> = programs
+        .iter()
+        .filter_map(|program| {
+            let output = lox(&["--ast", program.to_str().unwrap()]);
+            let expected = fs::read_to_string(program.with_extension("txt")).unwrap();
+            let actual = String::from_utf8_lossy(&output.stdout);
+            (!output.status.success() || actual != expected).then(|| {
+                format!(
+                    "{}: expected\n{}got\n{}{}",
+                    program.display(),
+                    expected,
+                    actual,
+                    String::from_utf8_lossy(&output.stderr)
+                )
+            })
+        })
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+/// Runs the golden program `name.lox`, which fails at runtime, and checks
+/// its error and trace against `name.txt`.
+fn assert_runtime_error(name: &str) {
+    let program = golden(&format!("{}.lox", name));
+    let output = lox(&[program.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(70), "{:?}", output);
+    let expected = fs::read_to_string(golden(&format!("{}.txt", name))).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), expected);
+}
+
+#[test]
+fn runtime_errors_print_the_calls_innermost_first() {
+    assert_runtime_error("trace");
+}
+
+#[test]
+fn uncaught_exceptions_print_the_calls_they_escaped() {
+    assert_runtime_error("uncaught");
+}
+
+#[test]
+fn runaway_recursion_prints_its_frame_once() {
+    assert_runtime_error("overflow");
+}
+
+/// `--check` never runs the program. It exits 0 with only warnings, which
+/// it prints, and 65 with errors.
+#[test]
+fn check_mode_matches_golden_output() {
+    for (name, code) in [
+        ("clean", 0),
+        ("warnings", 0),
+        ("unused", 0),
+        ("errors", 65),
+        ("syntax", 65),
+        ("import", 0),
+    ] {
+        let program = golden(&format!("check/{}.lox", name));
+        let output = lox(&["--check", program.to_str().unwrap()]);
+        assert_eq!(output.status.code(), Some(code), "{}: {:?}", name, output);
+        assert!(output.stdout.is_empty(), "{}: {:?}", name, output);
+        let expected = fs::read_to_string(golden(&format!("check/{}.txt", name))).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            expected,
+            "{}",
+            name
+        );
+    }
+}
+
+/// The same program goes by truthiness by default, and fails on the
+/// number it tests with `--strict-bool`.
+#[test]
+fn strict_bool_rejects_conditions_that_are_not_booleans() {
+    let program = golden("strict_bool.lox");
+    let output = lox(&[program.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "has items\ndone\n");
+
+    let output = lox(&["--strict-bool", program.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(70), "{:?}", output);
+    assert!(output.stdout.is_empty(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "[line 2] RuntimeError: Condition must be a boolean, got number.\n[line 2] in script\n"
+    );
+}
+
+#[test]
+fn bad_max_call_depth_is_a_usage_error() {
+    for arg in [
+        "--max-call-depth=many",
+        "--max-call-depth=0",
+        "--max-call-depth=100000",
+    ] {
+        let output = lox(&[arg, "missing.lox"]);
+        assert_eq!(output.status.code(), Some(64), "{}", arg);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "Usage error: --max-call-depth takes a number from 1 to 4096.\n"
+        );
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+#[test]
+fn json_dumps_need_the_serde_feature() {
+    for flag in ["--tokens-json", "--ast-json"] {
+        let output = lox(&[flag, "missing.lox"]);
+        assert_eq!(output.status.code(), Some(64));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!(
+                "Usage error: {} needs lox-rs built with the serde feature.\n",
+                flag
+            )
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ast_json_matches_golden_output() {
+    let program = golden("ast.lox");
+    let output = lox(&["--ast-json", program.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    let actual: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let expected: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(golden("ast.json")).unwrap()).unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ast_json_includes_type_annotations() {
+    fn annotations(node: &serde_json::Value, found: &mut Vec<(String, String)>) {
+        match node {
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    match (key.as_str(), value) {
+                        ("annotation" | "return_type", serde_json::Value::Object(annotation)) => {
+                            found.push((key.clone(), annotation["name"].to_string()))
+                        }
+                        _ => annotations(value, found),
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => {
+                values.iter().for_each(|value| annotations(value, found))
+            }
+            _ => {}
+        }
+    }
+
+    let program = golden("ast/annotations.lox");
+    let output = lox(&["--ast-json", program.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+    let ast: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut found = vec![];
+    annotations(&ast, &mut found);
+    let found: Vec<_> = found
+        .iter()
+        .map(|(key, name)| format!("{} {}", key, name))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            r#"annotation "Number""#,
+            r#"annotation "String""#,
+            r#"annotation "Number""#,
+            r#"annotation "Number""#,
+            r#"return_type "Number""#,
+            r#"annotation "Number""#,
+            r#"return_type "Bool""#,
+            r#"annotation "Number""#,
+            r#"annotation "Number""#,
+        ]
+    );
+}
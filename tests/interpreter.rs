@@ -0,0 +1,105 @@
+//! The interpreter as a library sees it, for embedders that drive the
+//! stages themselves.
+
+use lox_rs::ast::Program;
+use lox_rs::interpreter::{Interpreter, InterpreterOptions};
+use lox_rs::parser::Parser;
+use lox_rs::resolver::Locals;
+use lox_rs::scanner::Scanner;
+
+fn parse(source: &str) -> Program {
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    assert!(errors.is_empty(), "{:?}", errors);
+    Parser::parse(tokens).expect("the program parses")
+}
+
+// The resolver rejects a `return` at the top level, but without it the
+// `return` ends the program.
+#[test]
+fn top_level_return_ends_the_program() {
+    let program = parse("return; missing;");
+    let result = Interpreter::new().interpret(&program, Locals::default());
+    assert_eq!(result, Ok(()));
+}
+
+#[test]
+fn top_level_return_evaluates_its_value() {
+    let program = parse("fun f() { return missing; } return f(); f = nil;");
+    let error = Interpreter::new()
+        .interpret(&program, Locals::default())
+        .expect_err("the returned call fails");
+    assert_eq!(error.message, "Undefined variable 'missing'.");
+}
+
+#[test]
+fn runtime_errors_are_returned_with_their_line() {
+    for (source, message, line) in [
+        ("print\n-\"str\";", "Operand must be a number.", 2),
+        (
+            "print 1;\n\nprint missing;",
+            "Undefined variable 'missing'.",
+            3,
+        ),
+        (
+            "var f = 1;\nf(\n);",
+            "Can only call functions and classes.",
+            3,
+        ),
+    ] {
+        let error = Interpreter::new()
+            .interpret(&parse(source), Locals::default())
+            .expect_err(source);
+        assert_eq!(
+            (error.message.as_str(), error.line),
+            (message, line),
+            "{}",
+            source
+        );
+    }
+}
+
+// Without the resolver's locals, `super` isn't bound anywhere.
+#[test]
+fn unresolved_super_is_an_error() {
+    let program = parse("class A { m() {} } class B < A { m() { super.m(); } } B().m();");
+    let error = Interpreter::new()
+        .interpret(&program, Locals::default())
+        .expect_err("'super' is unresolved");
+    assert_eq!(error.message, "Undefined variable 'super'.");
+}
+
+#[test]
+fn ieee_division_is_an_option() {
+    let program = parse("var x = 1 / 0; var y = 1 % 0;");
+    let error = Interpreter::new()
+        .interpret(&program, Locals::default())
+        .expect_err("dividing by zero fails by default");
+    assert_eq!(error.message, "Division by zero.");
+
+    let options = InterpreterOptions {
+        ieee_division: true,
+        ..InterpreterOptions::default()
+    };
+    let result = Interpreter::new_with_options(options).interpret(&program, Locals::default());
+    assert_eq!(result, Ok(()));
+}
+
+#[test]
+fn runtime_errors_carry_the_calls_innermost_first() {
+    let program = parse(
+        "fun c() { return -nil; }\nfun b() { var x = c(); return x; }\nfun a() { b(); }\n\
+         var run = fun () { a(); };\nrun();",
+    );
+    let error = Interpreter::new()
+        .interpret(&program, Locals::default())
+        .expect_err("negating nil fails");
+    let frames: Vec<_> = error
+        .trace
+        .iter()
+        .map(|frame| (frame.function.as_str(), frame.line))
+        .collect();
+    assert_eq!(
+        frames,
+        [("c", 1), ("b", 2), ("a", 3), ("lambda", 4), ("script", 5)]
+    );
+}
@@ -0,0 +1,135 @@
+//! Runs each Lox program in `tests/programs` and checks it against the
+//! expectations written in its comments:
+//!
+//! - `// expect: text` for each line the program prints, in order;
+//! - `// expect runtime error: message` for the error it stops with, raised
+//!   on the line of the comment;
+//! - `// expect error: message` for a static error on the line of the
+//!   comment, which stops the program before it runs. The program must
+//!   report these errors and no others;
+//! - `// args: --flag ...` for options to run it with.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Exit codes of the driver, from `sysexits.h`.
+const EX_DATAERR: i32 = 65;
+const EX_SOFTWARE: i32 = 70;
+
+#[derive(Default)]
+struct Expectations {
+    args: Vec<String>,
+    output: Vec<String>,
+    runtime_error: Option<String>,
+    errors: Vec<(usize, String)>,
+}
+
+impl Expectations {
+    fn parse(source: &str) -> Self {
+        let mut expectations = Self::default();
+        for (i, line) in source.lines().enumerate() {
+            let line_number = i + 1;
+            let Some(comment) = line.find("// ").map(|at| &line[at + 3..]) else {
+                continue;
+            };
+            if let Some(text) = comment.strip_prefix("expect: ") {
+                expectations.output.push(text.to_owned());
+            } else if let Some(message) = comment.strip_prefix("expect runtime error: ") {
+                expectations.runtime_error =
+                    Some(format!("[line {}] RuntimeError: {}", line_number, message));
+            } else if let Some(message) = comment.strip_prefix("expect error: ") {
+                expectations.errors.push((line_number, message.to_owned()));
+            } else if let Some(args) = comment.strip_prefix("args: ") {
+                expectations
+                    .args
+                    .extend(args.split_whitespace().map(str::to_owned));
+            }
+        }
+        expectations
+    }
+}
+
+/// Runs the program at `path`, describing how it went wrong if it did.
+fn check(path: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let expected = Expectations::parse(&source);
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-rs"))
+        .args(&expected.args)
+        .arg(path)
+        .output()
+        .map_err(|error| error.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let printed: Vec<&str> = stdout.lines().collect();
+    if printed != expected.output {
+        return Err(format!(
+            "expected output {:?}, got {:?}\nstderr:\n{}",
+            expected.output, printed, stderr
+        ));
+    }
+
+    let code = output.status.code();
+    if !expected.errors.is_empty() {
+        if code != Some(EX_DATAERR) {
+            return Err(format!(
+                "expected static errors, got {:?}:\n{}",
+                code, stderr
+            ));
+        }
+        let reported = stderr
+            .lines()
+            .filter(|error| error.starts_with("Error: "))
+            .count();
+        if reported != expected.errors.len() {
+            return Err(format!(
+                "expected {} static errors, got {}:\n{}",
+                expected.errors.len(),
+                reported,
+                stderr
+            ));
+        }
+        for (line, message) in &expected.errors {
+            let prefix = format!("Error: [{}:", line);
+            if !stderr
+                .lines()
+                .any(|error| error.starts_with(&prefix) && error.ends_with(message.as_str()))
+            {
+                return Err(format!(
+                    "expected error on line {}: {}\nstderr:\n{}",
+                    line, message, stderr
+                ));
+            }
+        }
+    } else if let Some(error) = &expected.runtime_error {
+        if code != Some(EX_SOFTWARE) || stderr.lines().next() != Some(error.as_str()) {
+            return Err(format!("expected {}, got {:?}:\n{}", error, code, stderr));
+        }
+    } else if code != Some(0) || !stderr.is_empty() {
+        return Err(format!("expected success, got {:?}:\n{}", code, stderr));
+    }
+    Ok(())
+}
+
+#[test]
+fn programs() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .expect("Cannot read tests/programs")
+        .map(|entry| entry.expect("Cannot read tests/programs").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no programs in tests/programs");
+
+    let failures: Vec<String> = paths
+        .iter()
+        .filter_map(|path| {
+            check(path)
+                .err()
+                .map(|failure| format!("{}: {}", path.display(), failure))
+        })
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
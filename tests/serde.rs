@@ -0,0 +1,73 @@
+//! Serialization of the scanner's tokens with the `serde` feature. The
+//! crate builds and tests without it too.
+#![cfg(feature = "serde")]
+
+use lox_rs::scanner::Scanner;
+use lox_rs::token::Token;
+use serde_json::json;
+
+const PROGRAM: &str = r#"#!/usr/bin/env lox
+/// Doubles a number.
+fun double(n) {
+    return n * 2; // and nothing else
+}
+/* interpolated */ print "${double(21)} is ${"the" + " answer"}";
+var ratio = 2.5;
+"#;
+
+fn round_trip(tokens: &[Token]) -> Vec<Token> {
+    let json = serde_json::to_string(tokens).expect("tokens serialize");
+    serde_json::from_str(&json).expect("tokens deserialize")
+}
+
+#[test]
+fn tokens_round_trip() {
+    let (tokens, errors) = Scanner::new(PROGRAM).scan_tokens();
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(round_trip(&tokens), tokens);
+}
+
+#[test]
+fn tokens_round_trip_with_comments_and_trivia() {
+    let (tokens, errors) = Scanner::new(PROGRAM)
+        .with_trivia(true)
+        .with_lossless(true)
+        .scan_tokens();
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tokens.iter().any(|token| !token.trivia.is_empty()));
+    assert_eq!(round_trip(&tokens), tokens);
+}
+
+#[test]
+fn numbers_are_json_numbers_and_names_are_tagged() {
+    let (tokens, _) = Scanner::new(r#"count 12 2.5 "text" +"#).scan_tokens();
+    let kinds = serde_json::to_value(Token::kinds(&tokens)).expect("kinds serialize");
+    assert_eq!(
+        kinds,
+        json!([
+            {"Identifier": "count"},
+            {"Integer": 12},
+            {"Float": 2.5},
+            {"String": "text"},
+            "Plus",
+            "Eof",
+        ])
+    );
+}
+
+#[test]
+fn token_fields() {
+    let (tokens, _) = Scanner::new("  nil").scan_tokens();
+    let token = serde_json::to_value(&tokens[0]).expect("tokens serialize");
+    assert_eq!(
+        token,
+        json!({
+            "kind": "Nil",
+            "lexeme": "nil",
+            "line": 1,
+            "column": 3,
+            "span": {"start": 2, "end": 5},
+            "trivia": [],
+        })
+    );
+}
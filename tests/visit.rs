@@ -0,0 +1,86 @@
+//! A pass written outside the crate, which only overrides the nodes it
+//! cares about and lets the default walk reach the rest.
+
+use lox_rs::ast::{Arena, Expr, ExprKind, Function, Program};
+use lox_rs::parser::Parser;
+use lox_rs::scanner::Scanner;
+use lox_rs::visit::{walk_expr, walk_function, walk_stmts, Visitor};
+
+fn parse(source: &str) -> Program {
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    assert!(errors.is_empty(), "{:?}", errors);
+    Parser::parse(tokens).expect("the program parses")
+}
+
+/// The variables read, in the order the walk reaches them, and the
+/// functions entered.
+struct Reads<'a> {
+    exprs: &'a Arena,
+    variables: Vec<String>,
+    functions: Vec<String>,
+}
+
+impl<'a> Visitor<'a> for Reads<'a> {
+    fn exprs(&self) -> &'a Arena {
+        self.exprs
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Variable { name } = &expr.kind {
+            self.variables.push(name.lexeme.to_string());
+        }
+        walk_expr(self, expr);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.functions.push(function.name.lexeme.to_string());
+        walk_function(self, function);
+    }
+}
+
+fn reads(source: &str) -> (Vec<String>, Vec<String>) {
+    let program = parse(source);
+    let mut reads = Reads {
+        exprs: &program.exprs,
+        variables: vec![],
+        functions: vec![],
+    };
+    walk_stmts(&mut reads, &program.statements);
+    (reads.variables, reads.functions)
+}
+
+#[test]
+fn the_default_walk_reaches_every_expression_in_order() {
+    let (variables, _) = reads(
+        "print a + b;
+         if (c) { d; } else e;
+         while (f) g;
+         for (var x in h) [i, {j: k}];
+         switch (l) { case m: n; default: o; }
+         try { p; } catch (error) { q; } finally { r; }
+         throw s;
+         t.u = v[w] ?? x ? y : z;",
+    );
+    let expected: Vec<_> = "abcdefghijklmnopqrstvwxyz"
+        .chars()
+        .map(String::from)
+        .collect();
+    assert_eq!(variables, expected);
+}
+
+#[test]
+fn the_default_walk_enters_functions_methods_and_lambdas() {
+    let (variables, functions) = reads(
+        "fun outer(a = first) { return fun (b) { return second; }; }
+         class A < Base {
+           var field = third;
+           method() { fourth; }
+           static make() { fifth; }
+         }",
+    );
+    assert_eq!(functions, ["outer", "fun", "method", "make"]);
+    assert_eq!(
+        variables,
+        ["first", "second", "Base", "third", "fourth", "fifth"]
+    );
+}